@@ -1,43 +1,8 @@
-use std::collections::HashSet;
-
-/**
-    You notice that the device repeats the same frequency change list over and over. To calibrate the device, you need to find the first frequency it reaches twice.
-
-    For example, using the same list of changes above, the device would loop as follows:
-
-    Current frequency  0, change of +1; resulting frequency  1.
-    Current frequency  1, change of -2; resulting frequency -1.
-    Current frequency -1, change of +3; resulting frequency  2.
-    Current frequency  2, change of +1; resulting frequency  3.
-    (At this point, the device continues from the start of the list.)
-    Current frequency  3, change of +1; resulting frequency  4.
-    Current frequency  4, change of -2; resulting frequency  2, which has already been seen.
-    In this example, the first frequency reached twice is 2. Note that your device might need to repeat its list of frequency changes many times before a duplicate frequency is found, and that duplicates might be found while in the middle of processing the list.
-
-    Here are other examples:
-
-    +1, -1 first reaches 0 twice.
-    +3, +3, +4, -2, -4 first reaches 10 twice.
-    -6, +3, +8, +5, -6 first reaches 5 twice.
-    +7, +7, -2, -7, -4 first reaches 14 twice.
-    What is the first frequency your device reaches twice?
-*/
-
 fn main() -> std::io::Result<()> {
-    let mut freqs = HashSet::new();
-    let mut sum: i32 = 0;
-
-    let file = include_str!("../input");
-    loop {
-        for line in file.lines() {
-            let value = line.parse::<i32>().expect("Expected lines to be ints");
-            sum += value;
+    println!(
+        "Repeat: {:?}",
+        aoc2018_1b::first_repeat(include_str!("../input"))
+    );
 
-            /* insert on `HashSet` returns false if value is alreay in set */
-            if !freqs.insert(sum) {
-                println!("Repeat: {:?}", sum);
-                return Ok(());
-            }
-        }
-    }
+    Ok(())
 }
@@ -0,0 +1,58 @@
+/*
+    Confident that your list of box IDs is complete, you're ready to find the boxes full of prototype fabric.
+
+    The boxes will have IDs which differ by exactly one character at the same position in both strings. For example, given the following box IDs:
+
+    abcde
+    fghij
+    klmno
+    pqrst
+    fguij
+    axcye
+    wvxyz
+    The IDs abcde and axcye are close, but they differ by two characters (the second and fourth). However, the IDs fghij and fguij differ by exactly one character, the third (h and u). Those must be the correct boxes.
+
+    What letters are common between the two correct box IDs? (In the example above, this is found by removing the differing character from either ID, producing fgij.)
+*/
+
+/// Every pair of IDs that differ in exactly one position, with the letters they share.
+pub fn matching_pairs(vec: &[&str]) -> Vec<(usize, usize, String)> {
+    let mut pairs = Vec::new();
+
+    /* It is not ideal that the solution to this is a nested loop that permutates all the strings */
+    for i in 0..vec.len() {
+        'outer: for j in i + 1..vec.len() {
+            assert_eq!(vec[i].len(), vec[j].len());
+            let mut index: Option<usize> = None;
+            let mut chars_i = vec[i].chars();
+            let mut chars_j = vec[j].chars();
+
+            let wordlen = vec[i].len();
+            for pos in 0..wordlen {
+                if chars_i.next() != chars_j.next() {
+                    match index.as_mut() {
+                        Some(_val) => continue 'outer,
+                        None => index = Some(pos),
+                    }
+                }
+            }
+
+            let mut answer = String::from(vec[i]);
+            answer.remove(index.unwrap());
+
+            pairs.push((i, j, answer));
+        }
+    }
+
+    pairs
+}
+
+/// The letters shared by the first pair of IDs that differ in one position.
+pub fn common_letters(file: &str) -> Option<String> {
+    let lines: Vec<&str> = file.lines().collect();
+
+    matching_pairs(&lines)
+        .into_iter()
+        .next()
+        .map(|(_, _, answer)| answer)
+}
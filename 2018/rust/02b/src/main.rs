@@ -1,20 +1,3 @@
-/**
-    Confident that your list of box IDs is complete, you're ready to find the boxes full of prototype fabric.
-
-    The boxes will have IDs which differ by exactly one character at the same position in both strings. For example, given the following box IDs:
-
-    abcde
-    fghij
-    klmno
-    pqrst
-    fguij
-    axcye
-    wvxyz
-    The IDs abcde and axcye are close, but they differ by two characters (the second and fourth). However, the IDs fghij and fguij differ by exactly one character, the third (h and u). Those must be the correct boxes.
-
-    What letters are common between the two correct box IDs? (In the example above, this is found by removing the differing character from either ID, producing fgij.)
-*/
-
 fn main() -> std::io::Result<()> {
     let file = include_str!("../input");
     let mut vec = Vec::new();
@@ -24,32 +7,11 @@ fn main() -> std::io::Result<()> {
 
     println!("{} lines in input", vec.len());
 
-    /* It is not ideal that the solution to this is a nested loop that permutates all the strings */
-    for i in 0..vec.len() {
-        'outer: for j in i + 1..vec.len() {
-            assert_eq!(vec[i].len(), vec[j].len());
-            let mut index: Option<usize> = None;
-            let mut chars_i = vec[i].chars();
-            let mut chars_j = vec[j].chars();
-
-            let wordlen = vec[i].len();
-            for pos in 0..wordlen {
-                if chars_i.next() != chars_j.next() {
-                    match index.as_mut() {
-                        Some(_val) => continue 'outer,
-                        None => index = Some(pos),
-                    }
-                }
-            }
-
-            let mut answer = String::from(vec[i]);
-            answer.remove(index.unwrap());
-
-            println!("i: {}, j: {}", i, j);
-            println!("i: {}", vec[i]);
-            println!("j: {}", vec[j]);
-            println!("answer: {}", answer);
-        }
+    for (i, j, answer) in aoc2018_2b::matching_pairs(&vec) {
+        println!("i: {}, j: {}", i, j);
+        println!("i: {}", vec[i]);
+        println!("j: {}", vec[j]);
+        println!("answer: {}", answer);
     }
 
     Ok(())
@@ -0,0 +1,35 @@
+//! Times the feedback-loop search with interpreted and with compiled amplifiers.
+//!
+//! Run with `cargo bench -p aoc7b`.
+
+use intcode::amplifiers::Amplifiers;
+use intcode::compile::Native;
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 200;
+
+fn time(amplifiers: &Amplifiers) -> (i64, Duration) {
+    let start = Instant::now();
+    let mut best = 0;
+
+    for _ in 0..ROUNDS {
+        let search = aoc7b::search(amplifiers).expect("Bad topology");
+        assert!(search.failures.is_empty(), "{:?}", search.failures);
+        best = search.best.expect("No signal").1;
+    }
+
+    (best, start.elapsed() / ROUNDS)
+}
+
+fn main() {
+    let (interpreted, interpreter_time) = time(&aoc7b::interpreted(aoc7b::Amplifier::PROGRAM));
+    let (compiled, compiled_time) = time(&aoc7b::compiled());
+
+    assert_eq!(interpreted, compiled);
+    println!("interpreter: {:>10.1?} per search", interpreter_time);
+    println!("compiled:    {:>10.1?} per search", compiled_time);
+    println!(
+        "speedup:     {:>10.1}x",
+        interpreter_time.as_secs_f64() / compiled_time.as_secs_f64()
+    );
+}
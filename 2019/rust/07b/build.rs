@@ -0,0 +1,16 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    let program: Vec<i64> = fs::read_to_string("input")
+        .expect("Missing input")
+        .trim()
+        .split(',')
+        .map(|s| s.parse::<i64>().expect("Not an integer"))
+        .collect();
+
+    let out = Path::new(&env::var("OUT_DIR").expect("No OUT_DIR")).join("amplifier.rs");
+    fs::write(out, intcode::compile::to_rust(&program, "Amplifier")).expect("Write failed");
+    println!("cargo:rerun-if-changed=input");
+}
@@ -0,0 +1,157 @@
+use intcode::amplifiers::{AmplifierError, Amplifiers, Objective, Search, Topology};
+use intcode::compile::Native;
+use std::time::Duration;
+
+include!(concat!(env!("OUT_DIR"), "/amplifier.rs"));
+
+/*
+    --- Part Two ---
+    It's no good - in this configuration, the amplifiers can't generate a large enough output signal to produce the thrust you'll need. The Elves quickly talk you through rewiring the amplifiers into a feedback loop:
+
+          O-------O  O-------O  O-------O  O-------O  O-------O
+    0 -+->| Amp A |->| Amp B |->| Amp C |->| Amp D |->| Amp E |-.
+       |  O-------O  O-------O  O-------O  O-------O  O-------O |
+       |                                                        |
+       '--------------------------------------------------------+
+                                                                |
+                                                                v
+                                                         (to thrusters)
+    Most of the amplifiers are connected as they were before; amplifier A's output is connected to amplifier B's input, and so on. However, the output from amplifier E is now connected into amplifier A's input. This creates the feedback loop: the signal will be sent through the amplifiers many times.
+
+    In feedback loop mode, the amplifiers need totally different phase settings: integers from 5 to 9, again each used exactly once. These settings will cause the Amplifier Controller Software to repeatedly take input and produce output many times before halting. Provide each amplifier its phase setting at its first input instruction; all further input/output instructions are for signals.
+
+    Don't restart the Amplifier Controller Software on any amplifier during this process. Each one should continue receiving and sending signals until it halts.
+
+    All signals sent or received in this process will be between pairs of amplifiers except the very first signal and the very last signal. To start the process, a 0 signal is sent to amplifier A's input exactly once.
+
+    Eventually, the software on the amplifiers will halt after they have processed the final loop. When this happens, the last output signal from amplifier E is sent to the thrusters. Your job is to find the largest output signal that can be sent to the thrusters using the new phase settings and feedback loop arrangement.
+
+    Here are some example programs:
+
+    Max thruster signal 139629729 (from phase setting sequence 9,8,7,6,5):
+
+    3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,
+    27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5
+    Max thruster signal 18216 (from phase setting sequence 9,7,8,5,6):
+
+    3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,
+    -5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,
+    53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10
+    Try every combination of the new phase settings on the amplifier feedback loop. What is the highest signal that can be sent to the thrusters?
+*/
+
+/// The amplifiers wired in a feedback loop: compiled for the puzzle input, which the build
+/// script compiles into [`Amplifier`], and interpreted for any other program.
+pub fn amplifiers(program: &[i64]) -> Amplifiers<'_> {
+    if program == Amplifier::PROGRAM {
+        compiled()
+    } else {
+        interpreted(program)
+    }
+}
+
+/// The feedback loop running the compiled puzzle input.
+pub fn compiled() -> Amplifiers<'static> {
+    Amplifiers::compiled(Amplifier, Topology::Ring)
+}
+
+/// The feedback loop running `program` in the interpreter.
+pub fn interpreted(program: &[i64]) -> Amplifiers<'_> {
+    // A setting that sends the program into a loop fails instead of hanging the search.
+    Amplifiers::new(program, Topology::Ring).configure(|machine| {
+        machine.set_loop_detection(true);
+        machine.set_timeout(Some(Duration::from_secs(5)));
+    })
+}
+
+/// Tries every feedback-loop phase setting.
+pub fn search(amplifiers: &Amplifiers) -> Result<Search, AmplifierError> {
+    amplifiers.search(&[5, 6, 7, 8, 9], Objective::Maximize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Amplifier;
+    use intcode::amplifiers::{AmplifierError, Amplifiers, Topology};
+    use intcode::compile::{Compiled, Native};
+    use intcode::network::NetworkError;
+    use intcode::{Machine, MachineError, QueueTerminal, State};
+
+    #[test]
+    fn example_program1() {
+        let program = [
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let sequence = [9, 8, 7, 6, 5];
+        assert_eq!(
+            Ok(139629729),
+            Amplifiers::new(&program, Topology::Ring).signal(&sequence)
+        );
+    }
+
+    #[test]
+    fn example_program2() {
+        let program = [
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ];
+        let sequence = [9, 7, 8, 5, 6];
+        assert_eq!(
+            Ok(18216),
+            Amplifiers::new(&program, Topology::Ring).signal(&sequence)
+        );
+    }
+
+    #[test]
+    fn failing_amplifier() {
+        let program = [3, 15, 3, 16, 1008, 15, 7, 17, 1005, 17, 14, 4, 16, 99, 42];
+        let sequence = [5, 6, 7, 8, 9];
+        assert_eq!(
+            Err(AmplifierError::Failed(NetworkError::Failed {
+                node: 2,
+                error: MachineError::UnknownOpCode { pc: 14, value: 42 }
+            })),
+            Amplifiers::new(&program, Topology::Ring).signal(&sequence)
+        );
+    }
+
+    #[test]
+    fn compiled_amplifier() {
+        for phase in 5..10 {
+            let mut machine = Machine::new(Amplifier::PROGRAM);
+            let mut compiled = Compiled::new(Amplifier);
+            let mut interpreted = QueueTerminal::new(&[phase]);
+            let mut native = QueueTerminal::new(&[phase]);
+
+            for signal in 0.. {
+                let state = machine.resume(&mut interpreted);
+                assert_eq!(state, compiled.resume(&mut native));
+                assert_eq!(interpreted.outputs, native.outputs);
+                assert_eq!(machine.memory(), compiled.machine.memory());
+
+                if state != Ok(State::NeedsInput) {
+                    break;
+                }
+
+                interpreted.inputs.push_back(signal);
+                native.inputs.push_back(signal);
+            }
+
+            assert!(compiled.machine.terminated());
+            assert!(!compiled.self_modified());
+        }
+    }
+
+    #[test]
+    fn compiled_search() {
+        let input = intcode::loader::parse(include_str!("../input")).unwrap();
+        assert_eq!(input, Amplifier::PROGRAM);
+
+        let compiled = super::search(&super::compiled()).unwrap();
+        let interpreted = super::search(&super::interpreted(Amplifier::PROGRAM)).unwrap();
+        assert_eq!(compiled, interpreted);
+        assert_eq!(compiled.best, Some((vec![5, 7, 9, 8, 6], 8271623)));
+    }
+}
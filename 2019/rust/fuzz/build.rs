@@ -0,0 +1,50 @@
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+#[path = "src/generate.rs"]
+mod generate;
+
+/// Number of generated programs compiled to Rust, seeded 0, 1, 2, ...
+const CORPUS: usize = 64;
+
+fn main() {
+    let mut out = String::new();
+
+    for seed in 0..CORPUS {
+        let program = generate::generate(seed as u64);
+        out.push_str(&intcode::compile::to_rust(
+            &program,
+            &format!("Program{}", seed),
+        ));
+        out.push('\n');
+    }
+
+    writeln!(out, "/// Number of programs in the compiled corpus.").unwrap();
+    writeln!(out, "pub const CORPUS: usize = {};", CORPUS).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "/// Runs `generate(seed)` through its compiled code.").unwrap();
+    writeln!(
+        out,
+        "pub fn run_corpus(seed: usize, inputs: &[i64]) -> Run {{"
+    )
+    .unwrap();
+    writeln!(out, "match seed {{").unwrap();
+
+    for seed in 0..CORPUS {
+        writeln!(out, "{} => run_compiled(Program{}, inputs),", seed, seed).unwrap();
+    }
+
+    writeln!(
+        out,
+        "_ => panic!(\"Seed {{}} is not in the corpus\", seed),"
+    )
+    .unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    let path = Path::new(&env::var("OUT_DIR").expect("No OUT_DIR")).join("corpus.rs");
+    fs::write(path, out).expect("Write failed");
+    println!("cargo:rerun-if-changed=src/generate.rs");
+}
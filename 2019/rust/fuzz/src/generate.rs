@@ -0,0 +1,127 @@
+//! Random Intcode programs, shared with the build script that compiles the corpus.
+
+/// Number of cells after the code for instructions to read and write.
+const DATA: usize = 8;
+
+/// Opcodes to pick from, repeated to make them more likely. The last instruction is always a
+/// halt, so one in the middle only shows up now and then.
+const OPCODES: [i64; 15] = [1, 1, 2, 2, 3, 4, 4, 5, 5, 6, 6, 7, 8, 9, 99];
+
+/// xorshift64*, which is plenty to pick instructions with and keeps every seed reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn between(&mut self, low: i64, high: i64) -> i64 {
+        low + self.below((high - low + 1) as usize) as i64
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+fn parameters(opcode: i64) -> usize {
+    match opcode {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 | 9 => 1,
+        _ => 0,
+    }
+}
+
+/// The program for `seed`.
+///
+/// Every instruction is well-formed, and most read and write a small data area after the code.
+/// Some write into the code, jump backwards, jump through memory or move the relative base far
+/// enough to fail, so self-modification, loops and errors all get exercised.
+pub fn generate(seed: u64) -> Vec<i64> {
+    let mut rng = Rng::new(seed);
+    let count = 4 + rng.below(20);
+    let opcodes: Vec<i64> = (0..count)
+        .map(|i| match i + 1 {
+            last if last == count => 99,
+            _ => OPCODES[rng.below(OPCODES.len())],
+        })
+        .collect();
+
+    let mut addresses = Vec::with_capacity(count);
+    let mut end = 0;
+
+    for &opcode in &opcodes {
+        addresses.push(end as i64);
+        end += 1 + parameters(opcode);
+    }
+
+    let data = (end as i64, (end + DATA - 1) as i64);
+    let mut program = Vec::with_capacity(end + DATA);
+
+    for (i, &opcode) in opcodes.iter().enumerate() {
+        let mut modes = 0;
+        let mut values = Vec::new();
+
+        for position in 0..parameters(opcode) {
+            let writes = match opcode {
+                3 => position == 0,
+                1 | 2 | 7 | 8 => position == 2,
+                _ => false,
+            };
+            let jumps = (opcode == 5 || opcode == 6) && position == 1;
+
+            let (mode, value) = if jumps {
+                match rng.below(100) {
+                    0..=69 if i + 1 < count => (1, addresses[i + 1 + rng.below(count - i - 1)]),
+                    0..=84 => (1, addresses[rng.below(count)]),
+                    _ => (0, rng.between(data.0, data.1)),
+                }
+            } else if writes {
+                match rng.below(100) {
+                    0..=84 => (0, rng.between(data.0, data.1)),
+                    85..=94 => (0, rng.between(0, data.0 - 1)),
+                    _ => (2, rng.between(data.0, data.1)),
+                }
+            } else if opcode == 9 {
+                (1, rng.between(-3, 3))
+            } else {
+                match rng.below(100) {
+                    0..=39 => (1, rng.between(-5, 20)),
+                    40..=79 => (0, rng.between(data.0, data.1)),
+                    80..=89 => (0, rng.between(0, data.1)),
+                    _ => (2, rng.between(data.0, data.1)),
+                }
+            };
+
+            modes += mode * 10_i64.pow(position as u32);
+            values.push(value);
+        }
+
+        program.push(modes * 100 + opcode);
+        program.extend(values);
+    }
+
+    for _ in 0..DATA {
+        let value = if rng.chance(30) {
+            addresses[rng.below(count)]
+        } else {
+            rng.between(-3, 20)
+        };
+
+        program.push(value);
+    }
+
+    program
+}
@@ -0,0 +1,58 @@
+//! Differential testing of the Intcode backends.
+//!
+//! The same random programs and inputs go through the plain interpreter, the interpreter with
+//! its instruction cache on and, for a corpus of programs compiled to Rust by the build script,
+//! the compiled backend. Any difference in the result, the outputs or the final memory is a bug
+//! in one of them; the property tests live in `tests/differential.rs`.
+
+mod generate;
+
+pub use generate::generate;
+use intcode::compile::{Compiled, Native};
+use intcode::{Machine, MachineError, QueueTerminal, State};
+
+/// Most instructions an interpreted run may execute. Random programs often loop forever, and
+/// the step limit turns that into an error both interpreter modes must agree on.
+pub const STEP_LIMIT: u64 = 10_000;
+
+/// Everything a run leaves behind that the backends must agree on.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Run {
+    pub result: Result<State, MachineError>,
+    pub outputs: Vec<i64>,
+    pub memory: Vec<i64>,
+    pub steps: u64,
+}
+
+fn finish(result: Result<State, MachineError>, terminal: QueueTerminal, machine: &Machine) -> Run {
+    Run {
+        result,
+        outputs: terminal.outputs.into_iter().collect(),
+        memory: machine.memory().to_vec(),
+        steps: machine.steps(),
+    }
+}
+
+/// Runs `program` in the interpreter, with or without the instruction cache.
+pub fn interpret(program: &[i64], inputs: &[i64], cache: bool) -> Run {
+    let mut machine = Machine::new(program);
+    let mut terminal = QueueTerminal::new(inputs);
+
+    machine.set_cache(cache);
+    machine.set_step_limit(Some(STEP_LIMIT));
+
+    let result = machine.resume(&mut terminal);
+    finish(result, terminal, &machine)
+}
+
+/// Runs compiled code with no step limit, which would send it back to the interpreter, so
+/// only run programs known to stop.
+pub fn run_compiled<N: Native>(native: N, inputs: &[i64]) -> Run {
+    let mut compiled = Compiled::new(native);
+    let mut terminal = QueueTerminal::new(inputs);
+
+    let result = compiled.resume(&mut terminal);
+    finish(result, terminal, &compiled.machine)
+}
+
+include!(concat!(env!("OUT_DIR"), "/corpus.rs"));
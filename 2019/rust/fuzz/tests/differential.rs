@@ -0,0 +1,59 @@
+use intcode::{Instruction, MachineError};
+use intcode_fuzz::{generate, interpret, run_corpus, CORPUS};
+use proptest::prelude::*;
+
+fn inputs() -> impl Strategy<Value = Vec<i64>> {
+    prop::collection::vec(-3i64..20, 0..6)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn cache_matches_interpreter(seed in any::<u64>(), inputs in inputs()) {
+        let program = generate(seed);
+        prop_assert_eq!(interpret(&program, &inputs, true), interpret(&program, &inputs, false));
+    }
+
+    #[test]
+    fn compiled_matches_interpreter(seed in 0..CORPUS, inputs in inputs()) {
+        let reference = interpret(&generate(seed as u64), &inputs, false);
+
+        // Compiled code has no step limit, so only compare programs known to stop.
+        if let Err(MachineError::StepLimitExceeded { .. }) = reference.result {
+            return Ok(());
+        }
+
+        prop_assert_eq!(run_corpus(seed, &inputs), reference);
+    }
+}
+
+#[test]
+fn generated_programs_are_well_formed() {
+    for seed in 0..1000 {
+        let program = generate(seed);
+
+        // Eight data cells follow code that decodes instruction by instruction up to a halt.
+        let code = program.len() - 8;
+        let mut address = 0;
+
+        while address < code {
+            let instruction = Instruction::decode(&program, address).expect("Malformed");
+            address += instruction.size();
+        }
+
+        assert_eq!(address, code);
+        assert_eq!(program[code - 1], 99);
+    }
+}
+
+#[test]
+fn corpus_exercises_every_outcome() {
+    let runs: Vec<_> = (0..CORPUS)
+        .map(|seed| interpret(&generate(seed as u64), &[1, 2, 3], false))
+        .collect();
+
+    assert!(runs.iter().any(|run| run.result.is_ok()));
+    assert!(runs.iter().any(|run| run.result.is_err()));
+    assert!(runs.iter().any(|run| !run.outputs.is_empty()));
+}
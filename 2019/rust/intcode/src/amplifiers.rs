@@ -0,0 +1,467 @@
+//! Amplifier circuits and the search for their best phase settings.
+//!
+//! An amplifier circuit runs one copy of a program per amplifier, gives each its phase setting
+//! as first input, feeds a 0 signal into the first amplifier and reads the last value written by
+//! the output amplifier. How the amplifiers are wired is described by a [`Topology`].
+
+use crate::compile::Native;
+use crate::network::{Network, NetworkError, Outcome, Route};
+use crate::trace::Tracer;
+use crate::watch::Watcher;
+use crate::Machine;
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt;
+
+/// How amplifiers are wired together.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Topology {
+    /// Each amplifier feeds the next; the signal is the last amplifier's output.
+    Chain,
+    /// Like a chain, but the last amplifier also feeds the first.
+    Ring,
+    /// `edges[i]` lists the amplifiers that amplifier `i` feeds; the signal is the last value
+    /// written by amplifier `output`.
+    Graph {
+        edges: Vec<Vec<usize>>,
+        output: usize,
+    },
+}
+
+/// Which signal a search is after.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Objective {
+    Maximize,
+    Minimize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AmplifierError {
+    Failed(NetworkError),
+    /// Every amplifier that has not halted is waiting for input.
+    Deadlock,
+    /// The output amplifier never wrote anything.
+    NoSignal,
+    /// No phase settings were given, so there are no amplifiers to run.
+    NoAmplifiers,
+    /// The amplifiers cannot be wired as described for this many phase settings.
+    BadTopology(String),
+    /// A search was given more phase settings than it can try every ordering of.
+    TooManyPhases(usize),
+}
+
+impl fmt::Display for AmplifierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AmplifierError::Failed(NetworkError::Failed { node, error }) => {
+                write!(f, "amplifier {}: {}", node, error)
+            }
+            AmplifierError::Failed(error) => write!(f, "{}", error),
+            AmplifierError::Deadlock => write!(f, "amplifiers deadlocked"),
+            AmplifierError::NoSignal => write!(f, "no output signal"),
+            AmplifierError::NoAmplifiers => write!(f, "no amplifiers"),
+            AmplifierError::BadTopology(reason) => write!(f, "bad topology: {}", reason),
+            AmplifierError::TooManyPhases(count) => {
+                write!(f, "too many phase settings to permute: {}", count)
+            }
+        }
+    }
+}
+
+impl Error for AmplifierError {}
+
+impl From<NetworkError> for AmplifierError {
+    fn from(error: NetworkError) -> AmplifierError {
+        AmplifierError::Failed(error)
+    }
+}
+
+/// Every phase setting tried by [`Amplifiers::search`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Search {
+    /// The best setting and its signal. Ties go to the setting found first in lexicographic
+    /// order of the phase set as given.
+    pub best: Option<(Vec<i64>, i64)>,
+    pub failures: Vec<(Vec<i64>, AmplifierError)>,
+}
+
+type Runner<'a> = dyn Fn(&mut Network) -> Result<Outcome, NetworkError> + Sync + 'a;
+
+pub struct Amplifiers<'a> {
+    program: &'a [i64],
+    topology: Topology,
+    setup: Box<dyn Fn(&mut Machine) + Sync + 'a>,
+    runner: Box<Runner<'a>>,
+}
+
+impl<'a> Amplifiers<'a> {
+    pub fn new(program: &'a [i64], topology: Topology) -> Amplifiers<'a> {
+        Amplifiers {
+            program,
+            topology,
+            setup: Box::new(|_| {}),
+            runner: Box::new(Network::run),
+        }
+    }
+
+    /// Amplifiers running the program `native` was compiled from in its compiled code. A
+    /// machine that has a step limit, timeout or loop detection set up is interpreted anyway.
+    pub fn compiled<N: Native + Sync + 'a>(native: N, topology: Topology) -> Amplifiers<'a> {
+        Amplifiers {
+            runner: Box::new(move |network| network.run_compiled(&native)),
+            ..Amplifiers::new(N::PROGRAM, topology)
+        }
+    }
+
+    /// Calls `setup` on every amplifier's machine before it runs, for instance to put a step
+    /// limit, timeout or loop detector on it so one bad setting cannot hang a whole search.
+    pub fn configure(mut self, setup: impl Fn(&mut Machine) + Sync + 'a) -> Amplifiers<'a> {
+        self.setup = Box::new(setup);
+        self
+    }
+
+    /// Checks that `count` amplifiers can be wired up as the topology says.
+    fn check(&self, count: usize) -> Result<(), AmplifierError> {
+        if count == 0 {
+            return Err(AmplifierError::NoAmplifiers);
+        }
+
+        if let Topology::Graph { edges, output } = &self.topology {
+            let bad = |reason: String| Err(AmplifierError::BadTopology(reason));
+
+            if edges.len() != count {
+                return bad(format!(
+                    "graph has {} amplifiers but {} phase settings were given",
+                    edges.len(),
+                    count
+                ));
+            }
+
+            if *output >= count {
+                return bad(format!("output amplifier {} does not exist", output));
+            }
+
+            for (node, targets) in edges.iter().enumerate() {
+                if let Some(target) = targets.iter().find(|&&target| target >= count) {
+                    return bad(format!(
+                        "amplifier {} feeds missing amplifier {}",
+                        node, target
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn network(&self, phases: &[i64]) -> (Network, usize) {
+        let count = phases.len();
+        let routes: Vec<Route> = match &self.topology {
+            Topology::Chain => (0..count)
+                .map(|i| match i + 1 {
+                    next if next < count => Route::Nodes(vec![next]),
+                    _ => Route::Outside,
+                })
+                .collect(),
+            Topology::Ring => (0..count)
+                .map(|i| Route::Nodes(vec![(i + 1) % count]))
+                .collect(),
+            Topology::Graph { edges, .. } => {
+                edges.iter().map(|e| Route::Nodes(e.clone())).collect()
+            }
+        };
+        let output = match &self.topology {
+            Topology::Graph { output, .. } => *output,
+            _ => count - 1,
+        };
+
+        let mut network = Network::new();
+
+        for (route, &phase) in routes.into_iter().zip(phases) {
+            let mut machine = Machine::new(self.program);
+            (self.setup)(&mut machine);
+
+            let node = network.add(machine, route);
+            network.send(node, &[phase]);
+        }
+
+        network.send(0, &[0]);
+        (network, output)
+    }
+
+    fn run(
+        &self,
+        phases: &[i64],
+        run: impl FnOnce(&mut Network) -> Result<Outcome, NetworkError>,
+    ) -> Result<i64, AmplifierError> {
+        self.check(phases.len())?;
+
+        let (mut network, output) = self.network(phases);
+        let outcome = run(&mut network)?;
+
+        match outcome {
+            Outcome::Halted => network.last_output(output).ok_or(AmplifierError::NoSignal),
+            _ => Err(AmplifierError::Deadlock),
+        }
+    }
+
+    /// The signal produced with these phase settings, one per amplifier.
+    pub fn signal(&self, phases: &[i64]) -> Result<i64, AmplifierError> {
+        self.run(phases, &self.runner)
+    }
+
+    /// Like [`Amplifiers::signal`], tracing every amplifier.
+    pub fn signal_traced(
+        &self,
+        phases: &[i64],
+        tracer: &mut Tracer,
+    ) -> Result<i64, AmplifierError> {
+        self.run(phases, |network| network.run_traced(tracer))
+    }
+
+    /// Like [`Amplifiers::signal`], counting and watching the memory accesses of every
+    /// amplifier.
+    pub fn signal_watched(
+        &self,
+        phases: &[i64],
+        watcher: &mut Watcher,
+    ) -> Result<i64, AmplifierError> {
+        self.run(phases, |network| network.run_watched(watcher))
+    }
+
+    /// Tries every ordering of `phase_set` in parallel. Fails up front if the amplifiers cannot
+    /// be wired for that many settings, or if there are too many orderings to count.
+    pub fn search(
+        &self,
+        phase_set: &[i64],
+        objective: Objective,
+    ) -> Result<Search, AmplifierError> {
+        if phase_set.len() > 20 {
+            return Err(AmplifierError::TooManyPhases(phase_set.len()));
+        }
+
+        self.check(phase_set.len())?;
+
+        let total: u64 = (1..=phase_set.len() as u64).product();
+        let results: Vec<(Vec<i64>, Result<i64, AmplifierError>)> = (0..total)
+            .into_par_iter()
+            .map(|index| {
+                let phases = permutation(phase_set, index);
+                let signal = self.signal(&phases);
+                (phases, signal)
+            })
+            .collect();
+
+        let mut search = Search {
+            best: None,
+            failures: Vec::new(),
+        };
+
+        for (phases, result) in results {
+            match (result, &search.best) {
+                (Err(error), _) => search.failures.push((phases, error)),
+                (Ok(signal), Some((_, best)))
+                    if (objective == Objective::Maximize && signal <= *best)
+                        || (objective == Objective::Minimize && signal >= *best) => {}
+                (Ok(signal), _) => search.best = Some((phases, signal)),
+            }
+        }
+
+        Ok(search)
+    }
+}
+
+/// The `index`th permutation of `items` in lexicographic order of their positions.
+fn permutation(items: &[i64], mut index: u64) -> Vec<i64> {
+    let mut items = items.to_vec();
+    let mut permutation = Vec::with_capacity(items.len());
+
+    while !items.is_empty() {
+        let rest: u64 = (1..items.len() as u64).product();
+        permutation.push(items.remove((index / rest) as usize));
+        index %= rest;
+    }
+
+    permutation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{permutation, AmplifierError, Amplifiers, Objective, Topology};
+    use crate::network::NetworkError;
+    use crate::watch::{Action, Watch, Watcher};
+    use crate::MachineError;
+
+    const CHAIN: [i64; 17] = [
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ];
+
+    const FEEDBACK_LOOP: [i64; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    #[test]
+    fn permutations() {
+        let all: Vec<Vec<i64>> = (0..6).map(|i| permutation(&[1, 2, 3], i)).collect();
+        assert_eq!(
+            all,
+            [
+                [1, 2, 3],
+                [1, 3, 2],
+                [2, 1, 3],
+                [2, 3, 1],
+                [3, 1, 2],
+                [3, 2, 1]
+            ]
+        );
+    }
+
+    #[test]
+    fn chain_search() {
+        let amplifiers = Amplifiers::new(&CHAIN, Topology::Chain);
+        assert_eq!(amplifiers.signal(&[4, 3, 2, 1, 0]), Ok(43210));
+
+        let search = amplifiers
+            .search(&[0, 1, 2, 3, 4], Objective::Maximize)
+            .unwrap();
+        assert_eq!(search.best, Some((vec![4, 3, 2, 1, 0], 43210)));
+        assert!(search.failures.is_empty());
+
+        let search = amplifiers.search(&[0, 1, 2], Objective::Minimize).unwrap();
+        assert_eq!(search.best, Some((vec![0, 1, 2], 12)));
+    }
+
+    #[test]
+    fn ring_search() {
+        let amplifiers = Amplifiers::new(&FEEDBACK_LOOP, Topology::Ring);
+        let search = amplifiers
+            .search(&[5, 6, 7, 8, 9], Objective::Maximize)
+            .unwrap();
+        assert_eq!(search.best, Some((vec![9, 8, 7, 6, 5], 139629729)));
+    }
+
+    #[test]
+    fn watched() {
+        let amplifiers = Amplifiers::new(&FEEDBACK_LOOP, Topology::Ring);
+        let mut watcher = Watcher::new();
+        watcher.add(28, Watch::Write, Action::Pause);
+
+        // Every amplifier counts cell 28 down from 5 and none of them can stop the network.
+        assert_eq!(
+            amplifiers.signal_watched(&[9, 8, 7, 6, 5], &mut watcher),
+            Ok(139629729)
+        );
+        assert_eq!(watcher.log.len(), 25);
+        assert_eq!(watcher.heatmap.writes[&28], 25);
+        assert_eq!(watcher.heatmap.hottest_writes(1), [(27, 75)]);
+    }
+
+    #[test]
+    fn graph() {
+        // 0 feeds both 1 and 2, which each add their phase; 2 also feeds 3.
+        let add_phase = [3, 11, 3, 12, 1, 11, 12, 12, 4, 12, 99, 0, 0];
+        let edges = vec![vec![1, 2], vec![], vec![3], vec![]];
+
+        let amplifiers = Amplifiers::new(
+            &add_phase,
+            Topology::Graph {
+                edges: edges.clone(),
+                output: 3,
+            },
+        );
+        assert_eq!(amplifiers.signal(&[1, 10, 100, 1000]), Ok(1101));
+
+        let amplifiers = Amplifiers::new(
+            &add_phase,
+            Topology::Graph {
+                edges: edges.clone(),
+                output: 1,
+            },
+        );
+        assert_eq!(amplifiers.signal(&[1, 10, 100, 1000]), Ok(11));
+    }
+
+    #[test]
+    fn bad_topology() {
+        let graph = |edges: Vec<Vec<usize>>, output| {
+            Amplifiers::new(&[3, 0, 4, 0, 99], Topology::Graph { edges, output })
+        };
+        let bad = |reason: &str| Some(AmplifierError::BadTopology(reason.to_string()));
+
+        assert_eq!(
+            graph(vec![vec![1], vec![]], 1).signal(&[1, 2, 3]).err(),
+            bad("graph has 2 amplifiers but 3 phase settings were given")
+        );
+        assert_eq!(
+            graph(vec![vec![1], vec![]], 2).signal(&[1, 2]).err(),
+            bad("output amplifier 2 does not exist")
+        );
+        assert_eq!(
+            graph(vec![vec![1], vec![5]], 1)
+                .search(&[1, 2], Objective::Maximize)
+                .err(),
+            bad("amplifier 1 feeds missing amplifier 5")
+        );
+        assert_eq!(
+            graph(vec![], 0).signal(&[]),
+            Err(AmplifierError::NoAmplifiers)
+        );
+    }
+
+    #[test]
+    fn failures() {
+        let amplifiers = Amplifiers::new(&[3, 0, 4, 0, 42], Topology::Chain);
+        assert_eq!(
+            amplifiers.signal(&[1, 2]),
+            Err(AmplifierError::Failed(NetworkError::Failed {
+                node: 0,
+                error: MachineError::UnknownOpCode { pc: 4, value: 42 },
+            }))
+        );
+
+        let amplifiers = Amplifiers::new(&[3, 0, 3, 0, 99], Topology::Ring);
+        assert_eq!(amplifiers.signal(&[1, 2]), Err(AmplifierError::Deadlock));
+
+        let amplifiers = Amplifiers::new(&[3, 0, 3, 0, 99], Topology::Chain);
+        assert_eq!(amplifiers.signal(&[1]), Err(AmplifierError::NoSignal));
+
+        let search = amplifiers.search(&[1, 2], Objective::Maximize).unwrap();
+        assert_eq!(search.best, None);
+        assert_eq!(search.failures.len(), 2);
+
+        assert_eq!(amplifiers.signal(&[]), Err(AmplifierError::NoAmplifiers));
+        assert_eq!(
+            amplifiers.search(&[0; 21], Objective::Maximize),
+            Err(AmplifierError::TooManyPhases(21))
+        );
+
+        // Spins on its first jump once it has its phase and signal.
+        let spinning = [3, 0, 3, 0, 1105, 1, 4];
+        let amplifiers = Amplifiers::new(&spinning, Topology::Chain)
+            .configure(|machine| machine.set_loop_detection(true));
+        assert_eq!(
+            amplifiers.signal(&[1]),
+            Err(AmplifierError::Failed(NetworkError::Failed {
+                node: 0,
+                error: MachineError::InfiniteLoop {
+                    pc: 4,
+                    first: 4,
+                    last: 4,
+                    period: 1
+                },
+            }))
+        );
+
+        let limit = 50;
+        let amplifiers = Amplifiers::new(&spinning, Topology::Chain)
+            .configure(move |machine| machine.set_step_limit(Some(limit)));
+        assert_eq!(
+            amplifiers.signal(&[1]),
+            Err(AmplifierError::Failed(NetworkError::Failed {
+                node: 0,
+                error: MachineError::StepLimitExceeded { pc: 4, steps: 50 },
+            }))
+        );
+    }
+}
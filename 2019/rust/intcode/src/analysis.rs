@@ -0,0 +1,862 @@
+//! Static analysis of Intcode programs: the control-flow graph, its loops, which cells are code
+//! and which are data, writes that patch code, and where the program does input and output.
+//!
+//! Code is found the same way the [disassembler](crate::disasm) finds it, from pc 0. Jumps whose
+//! target is read from memory cannot be followed, so when a program has any, values in its data
+//! that point at well-formed code are taken as extra entry points. That is how the jump table of
+//! the day 7 amplifier controller is found, but it is a guess: any data value that happens to look
+//! like the address of an instruction is treated the same way.
+//!
+//! A cell that execution reaches but that only holds an instruction once another instruction has
+//! patched it, like the `1100` at 6 in the day 5 diagnostic program, is decoded in every form a
+//! change to its opcode digits can give it, and code is looked for after all of them.
+
+use crate::disasm::explore;
+use crate::instruction::Instruction;
+use crate::opcode::{OpCode, ParameterMode};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Write};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Edge {
+    /// On to the next instruction in memory.
+    FallThrough,
+    /// To the constant target of a jump.
+    Taken,
+}
+
+/// A run of instructions only ever entered at the first and left after the last.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Block {
+    pub instructions: Vec<Instruction>,
+    pub successors: Vec<(usize, Edge)>,
+    /// Whether the block ends in a jump whose target is read from memory.
+    pub dynamic: bool,
+}
+
+impl Block {
+    pub fn start(&self) -> usize {
+        self.instructions[0].address
+    }
+
+    /// The address just past the block's last instruction.
+    pub fn end(&self) -> usize {
+        let last = &self.instructions[self.instructions.len() - 1];
+        last.address + last.size()
+    }
+}
+
+/// A natural loop: every block that can reach a back edge to `header` without passing it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Loop {
+    pub header: usize,
+    pub blocks: BTreeSet<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RegionKind {
+    Code,
+    Data,
+}
+
+/// Cells `start..end`, all code or all data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Region {
+    pub start: usize,
+    pub end: usize,
+    pub kind: RegionKind,
+}
+
+/// A write with a constant address that lands inside an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Patch {
+    pub writer: usize,
+    pub target: usize,
+    pub instruction: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    /// pc 0, followed by the entry points guessed from data.
+    pub entries: Vec<usize>,
+    pub blocks: BTreeMap<usize, Block>,
+    pub loops: Vec<Loop>,
+    pub regions: Vec<Region>,
+    pub patches: Vec<Patch>,
+    /// Cells reached before they hold an instruction, with every instruction a patch to their
+    /// opcode digits turns them into. Blocks hold the first of these in the cell's place.
+    pub rewritten: BTreeMap<usize, Vec<Instruction>>,
+    /// Instructions writing relative to the relative base, which may land anywhere.
+    pub relative_writes: Vec<usize>,
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+}
+
+impl Analysis {
+    /// Instructions some other instruction may overwrite.
+    pub fn modified(&self) -> BTreeSet<usize> {
+        self.patches.iter().map(|patch| patch.instruction).collect()
+    }
+
+    fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.blocks
+            .values()
+            .flat_map(|block| block.instructions.iter())
+    }
+
+    /// The control-flow graph in Graphviz DOT.
+    ///
+    /// Entry blocks have a double border, blocks holding patched instructions are red, loop back
+    /// edges are bold and jumps through memory lead to a `?` node.
+    pub fn to_dot(&self) -> String {
+        let modified = self.modified();
+        let back_edges: BTreeSet<(usize, usize)> = self
+            .loops
+            .iter()
+            .flat_map(|l| {
+                l.blocks
+                    .iter()
+                    .filter(move |&&from| {
+                        self.blocks[&from]
+                            .successors
+                            .iter()
+                            .any(|&(to, _)| to == l.header)
+                    })
+                    .map(move |&from| (from, l.header))
+            })
+            .collect();
+
+        let mut dot = String::new();
+        dot.push_str("digraph intcode {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+
+        for (&start, block) in &self.blocks {
+            let mut label = String::new();
+
+            for instruction in &block.instructions {
+                let forms = match self.rewritten.get(&instruction.address) {
+                    Some(forms) => forms.as_slice(),
+                    None => std::slice::from_ref(instruction),
+                };
+
+                for form in forms {
+                    write!(label, "{:04}: {}\\l", instruction.address, form).unwrap();
+                }
+            }
+
+            let mut attributes = format!("label=\"{}\"", label);
+
+            if self.entries.contains(&start) {
+                attributes.push_str(", peripheries=2");
+            }
+
+            if block
+                .instructions
+                .iter()
+                .any(|instruction| modified.contains(&instruction.address))
+            {
+                attributes.push_str(", color=red");
+            }
+
+            writeln!(dot, "    b{} [{}];", start, attributes).unwrap();
+        }
+
+        for (&start, block) in &self.blocks {
+            for &(to, edge) in &block.successors {
+                let mut attributes = Vec::new();
+
+                if edge == Edge::Taken {
+                    attributes.push("label=\"taken\"");
+                }
+
+                if back_edges.contains(&(start, to)) {
+                    attributes.push("style=bold");
+                }
+
+                if attributes.is_empty() {
+                    writeln!(dot, "    b{} -> b{};", start, to).unwrap();
+                } else {
+                    let attributes = attributes.join(", ");
+                    writeln!(dot, "    b{} -> b{} [{}];", start, to, attributes).unwrap();
+                }
+            }
+
+            if block.dynamic {
+                writeln!(dot, "    b{} -> dynamic [style=dashed];", start).unwrap();
+            }
+        }
+
+        if self.blocks.values().any(|block| block.dynamic) {
+            dot.push_str("    dynamic [label=\"?\", shape=circle];\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn addresses(f: &mut fmt::Formatter, name: &str, addresses: &[usize]) -> fmt::Result {
+    if addresses.is_empty() {
+        return Ok(());
+    }
+
+    let addresses: Vec<String> = addresses.iter().map(|a| format!("{:04}", a)).collect();
+    writeln!(f, "{}: {}", name, addresses.join(", "))
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        addresses(f, "entries", &self.entries)?;
+        writeln!(f, "blocks: {}", self.blocks.len())?;
+
+        for region in &self.regions {
+            let kind = match region.kind {
+                RegionKind::Code => "code",
+                RegionKind::Data => "data",
+            };
+            writeln!(f, "{} {:04}..{:04}", kind, region.start, region.end)?;
+        }
+
+        for l in &self.loops {
+            let blocks: Vec<String> = l.blocks.iter().map(|b| format!("{:04}", b)).collect();
+            writeln!(f, "loop {:04}: {}", l.header, blocks.join(", "))?;
+        }
+
+        for patch in &self.patches {
+            writeln!(
+                f,
+                "patch: {:04} writes {:04} in {:04}",
+                patch.writer, patch.target, patch.instruction
+            )?;
+        }
+
+        for (address, forms) in &self.rewritten {
+            let forms: Vec<&str> = forms.iter().map(|form| form.opcode.mnemonic()).collect();
+            writeln!(f, "rewritten {:04}: {}", address, forms.join(", "))?;
+        }
+
+        addresses(f, "relative writes", &self.relative_writes)?;
+        addresses(f, "inputs", &self.inputs)?;
+        addresses(f, "outputs", &self.outputs)?;
+
+        let dynamic: Vec<usize> = self
+            .blocks
+            .values()
+            .filter(|block| block.dynamic)
+            .map(|block| block.instructions[block.instructions.len() - 1].address)
+            .collect();
+        addresses(f, "jumps through memory", &dynamic)
+    }
+}
+
+fn is_jump(instruction: &Instruction) -> bool {
+    matches!(instruction.opcode, OpCode::JumpIfTrue | OpCode::JumpIfNot)
+}
+
+/// Whether `instruction` jumps to an address read from memory.
+fn is_dynamic(instruction: &Instruction) -> bool {
+    is_jump(instruction)
+        && instruction.jump_target().is_none()
+        && instruction.parameters[1].mode != ParameterMode::Immediate
+}
+
+/// Where execution can go after `instruction`.
+fn successors(instruction: &Instruction) -> impl Iterator<Item = usize> {
+    let next =
+        Some(instruction.address + instruction.size()).filter(|_| instruction.falls_through());
+    next.into_iter().chain(instruction.jump_target())
+}
+
+/// The first cell execution reaches that does not hold an instruction but is the constant target
+/// of a write, with the instructions it can hold once patched.
+///
+/// Only the two opcode digits are changed, since those are what a patch adding a small value
+/// changes, and only forms that halt, jump, or run on into an instruction are kept.
+fn rewrite(
+    program: &[i64],
+    instructions: &HashMap<usize, Instruction>,
+    rewritten: &BTreeMap<usize, Vec<Instruction>>,
+) -> Option<(usize, Vec<Instruction>)> {
+    let mut code = vec![false; program.len()];
+
+    for instruction in instructions.values() {
+        let end = (instruction.address + instruction.size()).min(program.len());
+
+        for cell in code[instruction.address..end].iter_mut() {
+            *cell = true;
+        }
+    }
+
+    let all = || instructions.values().chain(rewritten.values().flatten());
+    let written: BTreeSet<usize> = all()
+        .filter_map(|instruction| {
+            let target = instruction.parameters[instruction.opcode.target()? - 1];
+            Some(target.value as usize)
+                .filter(|_| target.mode == ParameterMode::Position && target.value >= 0)
+        })
+        .collect();
+    let reached: BTreeSet<usize> = all().flat_map(successors).collect();
+
+    let free = |start: usize, end: usize| end <= program.len() && !code[start..end].contains(&true);
+
+    reached
+        .into_iter()
+        .filter(|&address| {
+            free(address, address + 1)
+                && program[address] >= 0
+                && written.contains(&address)
+                && Instruction::decode(program, address).is_none()
+        })
+        .find_map(|address| {
+            let modes = program[address] - program[address] % 100;
+            let mut memory = program.to_vec();
+
+            let forms: Vec<Instruction> = (1..100)
+                .filter_map(|digits| {
+                    memory[address] = modes + digits;
+                    Instruction::decode(&memory, address)
+                })
+                .filter(|form| {
+                    let next = form.address + form.size();
+                    free(address, next)
+                        && (!form.falls_through()
+                            || instructions.contains_key(&next)
+                            || (free(next, next + 1)
+                                && Instruction::decode(program, next).is_some()))
+                })
+                .collect();
+
+            Some((address, forms)).filter(|(_, forms)| !forms.is_empty())
+        })
+}
+
+/// The first address held in a data cell of `program` that starts a well-formed instruction
+/// clear of the code decoded so far.
+fn guess(program: &[i64], instructions: &HashMap<usize, Instruction>) -> Option<usize> {
+    let mut code = vec![false; program.len()];
+
+    for instruction in instructions.values() {
+        let end = (instruction.address + instruction.size()).min(program.len());
+
+        for cell in code[instruction.address..end].iter_mut() {
+            *cell = true;
+        }
+    }
+
+    program
+        .iter()
+        .enumerate()
+        .filter(|&(cell, &value)| !code[cell] && value >= 0 && (value as usize) < program.len())
+        .map(|(_, &value)| value as usize)
+        .find(|&address| match Instruction::decode(program, address) {
+            // Like `explore`, which would refuse an instruction overlapping known code and
+            // leave this address to be guessed again.
+            Some(instruction) => {
+                let end = (address + instruction.size()).min(program.len());
+                !code[address..end].contains(&true)
+            }
+            None => false,
+        })
+}
+
+fn blocks(
+    instructions: &HashMap<usize, Instruction>,
+    rewritten: &BTreeMap<usize, Vec<Instruction>>,
+    entries: &[usize],
+) -> BTreeMap<usize, Block> {
+    let mut leaders: BTreeSet<usize> = entries.iter().cloned().collect();
+
+    for instruction in instructions.values() {
+        if is_jump(instruction) {
+            leaders.insert(instruction.address + instruction.size());
+            leaders.extend(instruction.jump_target());
+        }
+    }
+
+    for form in rewritten.values().flatten() {
+        leaders.insert(form.address + form.size());
+        leaders.extend(form.jump_target());
+    }
+
+    let mut addresses: Vec<usize> = instructions.keys().cloned().collect();
+    addresses.sort_unstable();
+
+    let mut blocks: BTreeMap<usize, Block> = BTreeMap::new();
+    let mut current: Option<Block> = None;
+
+    for address in addresses {
+        let instruction = instructions[&address].clone();
+
+        if let Some(block) = current.take() {
+            let last = &block.instructions[block.instructions.len() - 1];
+
+            // Anything but the straight continuation of a non-jump starts a new block.
+            if leaders.contains(&address)
+                || block.end() != address
+                || is_jump(last)
+                || last.opcode == OpCode::End
+                || rewritten.contains_key(&last.address)
+            {
+                blocks.insert(block.start(), block);
+            } else {
+                current = Some(block);
+            }
+        }
+
+        current
+            .get_or_insert_with(|| Block {
+                instructions: Vec::new(),
+                successors: Vec::new(),
+                dynamic: false,
+            })
+            .instructions
+            .push(instruction);
+    }
+
+    if let Some(block) = current {
+        blocks.insert(block.start(), block);
+    }
+
+    for block in blocks.values_mut() {
+        let last = &block.instructions[block.instructions.len() - 1];
+        let forms = match rewritten.get(&last.address) {
+            Some(forms) => forms.as_slice(),
+            None => std::slice::from_ref(last),
+        };
+        let mut successors = Vec::new();
+
+        let taken = forms.iter().filter_map(Instruction::jump_target);
+        let next = forms
+            .iter()
+            .filter(|form| form.falls_through())
+            .map(|form| form.address + form.size());
+
+        for successor in taken
+            .map(|target| (target, Edge::Taken))
+            .chain(next.map(|end| (end, Edge::FallThrough)))
+        {
+            if !successors.contains(&successor) {
+                successors.push(successor);
+            }
+        }
+
+        block.dynamic = forms.iter().any(is_dynamic);
+        block.successors = successors
+            .into_iter()
+            .filter(|(to, _)| instructions.contains_key(to))
+            .collect();
+    }
+
+    blocks
+}
+
+fn loops(blocks: &BTreeMap<usize, Block>, entries: &[usize]) -> Vec<Loop> {
+    let mut predecessors: HashMap<usize, Vec<usize>> = HashMap::new();
+
+    for (&from, block) in blocks {
+        for &(to, _) in &block.successors {
+            predecessors.entry(to).or_default().push(from);
+        }
+    }
+
+    // Depth-first search for back edges: edges to a block still on the search path.
+    let mut visited = BTreeSet::new();
+    let mut on_path = BTreeSet::new();
+    let mut back_edges = Vec::new();
+
+    // An entry holding no instruction starts no block.
+    for &entry in entries.iter().filter(|entry| blocks.contains_key(entry)) {
+        if !visited.insert(entry) {
+            continue;
+        }
+
+        on_path.insert(entry);
+        let mut stack = vec![(entry, 0)];
+
+        while let Some((block, next)) = stack.pop() {
+            match blocks[&block].successors.get(next) {
+                Some(&(to, _)) => {
+                    stack.push((block, next + 1));
+
+                    if on_path.contains(&to) {
+                        back_edges.push((block, to));
+                    } else if visited.insert(to) {
+                        on_path.insert(to);
+                        stack.push((to, 0));
+                    }
+                }
+                None => {
+                    on_path.remove(&block);
+                }
+            }
+        }
+    }
+
+    let mut loops: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+
+    for (latch, header) in back_edges {
+        let body = loops.entry(header).or_default();
+        body.insert(header);
+        let mut pending = vec![latch];
+
+        while let Some(block) = pending.pop() {
+            if body.insert(block) {
+                pending.extend(predecessors.get(&block).into_iter().flatten());
+            }
+        }
+    }
+
+    loops
+        .into_iter()
+        .map(|(header, blocks)| Loop { header, blocks })
+        .collect()
+}
+
+fn regions(program: &[i64], instructions: &HashMap<usize, Instruction>) -> Vec<Region> {
+    let mut regions: Vec<Region> = Vec::new();
+    let mut address = 0;
+
+    while address < program.len() {
+        let (kind, end) = match instructions.get(&address) {
+            Some(instruction) => (RegionKind::Code, address + instruction.size()),
+            None => (RegionKind::Data, address + 1),
+        };
+        let end = end.min(program.len());
+
+        match regions.last_mut() {
+            Some(region) if region.kind == kind => region.end = end,
+            _ => regions.push(Region {
+                start: address,
+                end,
+                kind,
+            }),
+        }
+
+        address = end;
+    }
+
+    regions
+}
+
+/// Analyzes `program` as loaded, before it has run.
+pub fn analyze(program: &[i64]) -> Analysis {
+    let mut instructions = HashMap::new();
+    let mut rewritten = BTreeMap::new();
+    let mut entries = vec![0];
+    explore(program, &mut instructions, &entries);
+
+    // Rewrite one patched cell or guess one entry point at a time, so that code found from one
+    // is no longer taken for data when looking for the next.
+    loop {
+        if let Some((address, forms)) = rewrite(program, &instructions, &rewritten) {
+            let next: Vec<usize> = forms.iter().flat_map(successors).collect();
+            instructions.insert(address, forms[0].clone());
+            rewritten.insert(address, forms);
+            explore(program, &mut instructions, &next);
+            continue;
+        }
+
+        let dynamic = instructions
+            .values()
+            .chain(rewritten.values().flatten())
+            .any(is_dynamic);
+
+        if !dynamic {
+            break;
+        }
+
+        match guess(program, &instructions) {
+            Some(address) => {
+                explore(program, &mut instructions, &[address]);
+                entries.push(address);
+            }
+            None => break,
+        }
+    }
+
+    let blocks = blocks(&instructions, &rewritten, &entries);
+    let loops = loops(&blocks, &entries);
+
+    let mut owner: Vec<Option<usize>> = vec![None; program.len()];
+
+    for instruction in instructions.values() {
+        let end = (instruction.address + instruction.size()).min(program.len());
+
+        for cell in owner[instruction.address..end].iter_mut() {
+            *cell = Some(instruction.address);
+        }
+    }
+
+    let mut analysis = Analysis {
+        regions: regions(program, &instructions),
+        entries,
+        blocks,
+        loops,
+        rewritten,
+        ..Analysis::default()
+    };
+
+    let mut patches = Vec::new();
+    let mut relative_writes = Vec::new();
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+
+    for instruction in analysis.instructions() {
+        match instruction.opcode {
+            OpCode::Input => inputs.push(instruction.address),
+            OpCode::Output => outputs.push(instruction.address),
+            _ => {}
+        }
+
+        let target = match instruction.opcode.target() {
+            Some(position) => instruction.parameters[position - 1],
+            None => continue,
+        };
+
+        match target.mode {
+            ParameterMode::Relative => relative_writes.push(instruction.address),
+            _ if target.value < 0 => {}
+            _ => {
+                let cell = target.value as usize;
+
+                if let Some(&Some(patched)) = owner.get(cell) {
+                    patches.push(Patch {
+                        writer: instruction.address,
+                        target: cell,
+                        instruction: patched,
+                    });
+                }
+            }
+        }
+    }
+
+    analysis.patches = patches;
+    analysis.relative_writes = relative_writes;
+    analysis.inputs = inputs;
+    analysis.outputs = outputs;
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze, Edge, Loop, Patch, Region, RegionKind};
+    use crate::examples::{COUNTDOWN, EXAMPLES};
+    use crate::loader;
+    use crate::opcode::OpCode;
+
+    #[test]
+    fn jump_program() {
+        let analysis = analyze(EXAMPLES[9]);
+        let starts: Vec<usize> = analysis.blocks.keys().cloned().collect();
+        assert_eq!(starts, [0, 9, 16, 22, 31, 36, 46]);
+
+        assert_eq!(
+            analysis.blocks[&0].successors,
+            [(22, Edge::Taken), (9, Edge::FallThrough)]
+        );
+        assert_eq!(analysis.blocks[&16].successors, [(36, Edge::Taken)]);
+        assert!(analysis.blocks[&46].successors.is_empty());
+
+        assert_eq!(
+            analysis.regions,
+            [
+                Region {
+                    start: 0,
+                    end: 19,
+                    kind: RegionKind::Code
+                },
+                Region {
+                    start: 19,
+                    end: 22,
+                    kind: RegionKind::Data
+                },
+                Region {
+                    start: 22,
+                    end: 45,
+                    kind: RegionKind::Code
+                },
+                Region {
+                    start: 45,
+                    end: 46,
+                    kind: RegionKind::Data
+                },
+                Region {
+                    start: 46,
+                    end: 47,
+                    kind: RegionKind::Code
+                },
+            ]
+        );
+
+        assert!(analysis.loops.is_empty());
+        assert!(analysis.patches.is_empty());
+        assert_eq!(analysis.inputs, [0]);
+        assert_eq!(analysis.outputs, [26, 31, 40]);
+    }
+
+    #[test]
+    fn countdown_loop() {
+        let analysis = analyze(COUNTDOWN);
+        let starts: Vec<usize> = analysis.blocks.keys().cloned().collect();
+        assert_eq!(starts, [0, 2, 11]);
+
+        assert_eq!(
+            analysis.loops,
+            [Loop {
+                header: 2,
+                blocks: vec![2].into_iter().collect()
+            }]
+        );
+        assert!(analysis
+            .to_dot()
+            .contains("b2 -> b2 [label=\"taken\", style=bold];"));
+    }
+
+    #[test]
+    fn self_modification() {
+        // Counts to 3 by incrementing the operand of its own output instruction.
+        let program = [104, 0, 1001, 1, 1, 1, 1007, 1, 3, 14, 1005, 14, 0, 99, 0];
+        let analysis = analyze(&program);
+
+        assert_eq!(
+            analysis.patches,
+            [Patch {
+                writer: 2,
+                target: 1,
+                instruction: 0
+            }]
+        );
+        assert_eq!(analysis.modified().into_iter().collect::<Vec<_>>(), [0]);
+        assert!(analysis.to_dot().contains("b0 [label=\"0000: OUT #0\\l"));
+    }
+
+    #[test]
+    fn jump_table() {
+        // Reads an index, adds the table base and jumps through the patched operand.
+        let program = [
+            3, 8, 1001, 8, 10, 8, 105, 1, 0, 0, 12, 16, 104, 1, 99, 0, 104, 2, 99,
+        ];
+        let analysis = analyze(&program);
+
+        assert_eq!(analysis.entries, [0, 12, 16]);
+        assert!(analysis.blocks[&0].dynamic);
+        assert_eq!(
+            analysis.patches,
+            [
+                Patch {
+                    writer: 0,
+                    target: 8,
+                    instruction: 6
+                },
+                Patch {
+                    writer: 2,
+                    target: 8,
+                    instruction: 6
+                },
+            ]
+        );
+        assert_eq!(analysis.outputs, [12, 16]);
+
+        let dot = analysis.to_dot();
+        assert!(dot.contains("b0 -> dynamic [style=dashed];"));
+        assert!(dot.contains("b12 [label=\"0012: OUT #1\\l0014: HLT\\l\", peripheries=2];"));
+    }
+
+    #[test]
+    fn data_only() {
+        for program in [&[0][..], &[42, 1, 2], &[]].iter() {
+            let analysis = analyze(program);
+
+            assert!(analysis.blocks.is_empty());
+            assert!(analysis.loops.is_empty());
+            assert!(analysis
+                .regions
+                .iter()
+                .all(|region| region.kind == RegionKind::Data));
+            assert_eq!(
+                analysis.to_dot(),
+                "digraph intcode {\n    node [shape=box, fontname=\"monospace\"];\n}\n"
+            );
+        }
+    }
+
+    #[test]
+    fn overlapping_guess() {
+        // Cell 11 points into the middle of the ADD at 3, which must not be guessed forever.
+        let program = [1105, 1, 5, 1101, 0, 104, 50, 5, 10, 11, 99, 3];
+        let analysis = analyze(&program);
+
+        assert_eq!(analysis.entries, [0]);
+        assert_eq!(
+            analysis.blocks.keys().cloned().collect::<Vec<_>>(),
+            [0, 5, 10]
+        );
+    }
+
+    #[test]
+    fn rewritten() {
+        // Turns the 0 after the input into an output instruction.
+        let program = [3, 9, 1, 9, 6, 6, 0, 7, 99, 0];
+        let analysis = analyze(&program);
+
+        let forms: Vec<OpCode> = analysis.rewritten[&6].iter().map(|i| i.opcode).collect();
+        assert_eq!(
+            forms,
+            [
+                OpCode::Input,
+                OpCode::Output,
+                OpCode::AdjustRelativeBase,
+                OpCode::End
+            ]
+        );
+        assert_eq!(analysis.blocks[&0].successors, [(8, Edge::FallThrough)]);
+        assert_eq!(
+            analysis.regions.last().map(|r| r.kind),
+            Some(RegionKind::Data)
+        );
+        assert!(analysis.to_dot().contains("0006: OUT [7]\\l"));
+    }
+
+    #[test]
+    fn diagnostic_program() {
+        // The day 5 program patches the 1100 at 6 with its input: 1 adds, 5 jumps to 238.
+        let program = loader::parse(include_str!("../../05a/input")).expect("Bad program");
+        let analysis = analyze(&program);
+
+        assert!(analysis
+            .to_string()
+            .contains("rewritten 0006: add, mul, jt, lt, eq\n"));
+        assert_eq!(
+            analysis.blocks[&0].successors,
+            [(238, Edge::Taken), (10, Edge::FallThrough)]
+        );
+        assert_eq!(
+            analysis.regions[0],
+            Region {
+                start: 0,
+                end: 223,
+                kind: RegionKind::Code
+            }
+        );
+        assert_eq!(
+            analysis.regions.last(),
+            Some(&Region {
+                start: 677,
+                end: 678,
+                kind: RegionKind::Data
+            })
+        );
+        assert_eq!(analysis.outputs.len(), 11);
+    }
+
+    #[test]
+    fn examples() {
+        for program in EXAMPLES {
+            let analysis = analyze(program);
+            let covered: usize = analysis.regions.iter().map(|r| r.end - r.start).sum();
+
+            assert_eq!(covered, program.len());
+            assert!(analysis.to_dot().starts_with("digraph intcode {\n"));
+        }
+    }
+}
@@ -0,0 +1,882 @@
+//! Assembles Intcode from a small text syntax.
+//!
+//! ```text
+//! ; Echo numbers until a zero is read.
+//! loop:   in -> [value]
+//!         jf [value], #done
+//!         out [value]
+//!         jt #1, #loop
+//! done:   hlt
+//! value:  .data 0
+//! ```
+//!
+//! Every parameter carries its mode: `#x` is immediate, `[x]` is position and `[r-3]` or
+//! `[r+x]` is relative to the relative base. Values are integers, labels, or a label plus or
+//! minus an integer. The write target may be separated by `->` instead of a comma, and a numeric
+//! prefix such as `0012:` asserts the address of the line, so the output of
+//! [`disassemble`](crate::disasm::disassemble) assembles back to the original image.
+//!
+//! Larger programs can use the relative base as a stack pointer, pointing at the first free
+//! cell. A program that does starts with `arb #stack` and ends with a `stack:` label, after
+//! which memory is free to grow. On top of that there are pseudo-instructions:
+//!
+//! - `push x` copies `x` to the top of the stack and `pop -> x` copies it back off.
+//! - `call #f` pushes the return address and jumps to `f`, and `ret` pops it and jumps back.
+//!   Arguments pushed before a call are at `[r-2]` and below inside the function.
+//! - `drop n` discards `n` values from the stack.
+//!
+//! Relative operands of `push` and `pop` refer to the stack as it was before the instruction.
+//!
+//! `.ascii "text"` emits the character codes of a string and `.asciz` adds a zero after them.
+//! `.macro name a, b` starts a macro that runs to `.endm`; using `name x, y` as an instruction
+//! expands its body with `%a` and `%b` replaced by `x` and `y`, and `%@` by a number unique to
+//! the expansion for labels of its own; it may not be named after an instruction,
+//! pseudo-instruction or directive. Labels starting with `__` are left to the assembler. `.include "name"` assembles another source in place:
+//! `"std"` is the [standard library](STD) and anything else is resolved by the caller of
+//! [`assemble_with`].
+
+use crate::opcode::{OpCode, ParameterMode};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AsmError {
+    /// One-based source line the error was found on.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(i64),
+    Label(String, i64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Operand {
+    mode: ParameterMode,
+    value: Value,
+}
+
+#[derive(Debug)]
+enum Item {
+    Instruction(OpCode, Vec<Operand>),
+    Data(Vec<Value>),
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::Instruction(opcode, _) => opcode.size(),
+            Item::Data(values) => values.len(),
+        }
+    }
+}
+
+/// Pseudo-instructions and directives, which a macro may not take the name of any more than an
+/// instruction's mnemonic.
+const RESERVED: [&str; 11] = [
+    "push", "pop", "call", "ret", "drop", ".macro", ".endm", ".include", ".ascii", ".asciz",
+    ".data",
+];
+
+/// The prefix of labels made up by the assembler, which source labels may not start with.
+const GENERATED: &str = "__";
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let text = text.trim();
+
+    if let Ok(number) = text.parse::<i64>() {
+        return Ok(Value::Number(number));
+    }
+
+    let (label, offset) = match text.rfind(['+', '-']) {
+        Some(split) if split > 0 => {
+            let offset = text[split..]
+                .replace('+', "")
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| format!("invalid offset in `{}`", text))?;
+            (text[..split].trim(), offset)
+        }
+        _ => (text, 0),
+    };
+
+    if is_identifier(label) {
+        Ok(Value::Label(label.to_string(), offset))
+    } else {
+        Err(format!("invalid value `{}`", text))
+    }
+}
+
+fn parse_operand(text: &str) -> Result<Operand, String> {
+    let text = text.trim();
+
+    if let Some(value) = text.strip_prefix('#') {
+        return Ok(Operand {
+            mode: ParameterMode::Immediate,
+            value: parse_value(value)?,
+        });
+    }
+
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(|| format!("operand `{}` needs a mode: #x, [x] or [rx]", text))?
+        .trim();
+
+    if let Some(offset) = inner.strip_prefix('r') {
+        let offset = offset.trim_start();
+
+        if offset.starts_with('+') || offset.starts_with('-') || offset.parse::<i64>().is_ok() {
+            return Ok(Operand {
+                mode: ParameterMode::Relative,
+                value: parse_value(offset.strip_prefix('+').unwrap_or(offset))?,
+            });
+        }
+    }
+
+    Ok(Operand {
+        mode: ParameterMode::Position,
+        value: parse_value(inner)?,
+    })
+}
+
+fn parse_item(text: &str) -> Result<Item, String> {
+    let (word, rest) = match text.find(char::is_whitespace) {
+        Some(split) => (&text[..split], text[split..].trim()),
+        None => (text, ""),
+    };
+    let rest = rest.strip_prefix("->").unwrap_or(rest);
+
+    let fields: Vec<&str> = if rest.is_empty() {
+        Vec::new()
+    } else {
+        rest.split(',')
+            .flat_map(|field| field.split("->"))
+            .collect()
+    };
+
+    if word == ".data" {
+        if fields.is_empty() {
+            return Err(".data needs at least one value".to_string());
+        }
+
+        return Ok(Item::Data(
+            fields
+                .into_iter()
+                .map(parse_value)
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+
+    let opcode =
+        OpCode::from_mnemonic(word).ok_or_else(|| format!("unknown mnemonic `{}`", word))?;
+
+    if fields.len() != opcode.parameters() {
+        return Err(format!(
+            "`{}` takes {} parameters, found {}",
+            opcode.mnemonic(),
+            opcode.parameters(),
+            fields.len()
+        ));
+    }
+
+    let operands = fields
+        .into_iter()
+        .map(parse_operand)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(target) = opcode.target() {
+        if operands[target - 1].mode == ParameterMode::Immediate {
+            return Err(format!(
+                "`{}` cannot write to an immediate parameter",
+                opcode.mnemonic()
+            ));
+        }
+    }
+
+    Ok(Item::Instruction(opcode, operands))
+}
+
+/// The standard library: `print_number`, `print_string`, `read_line`, `power` and
+/// `factorial`, following the calling convention of `call`.
+pub const STD: &str = include_str!("std.asm");
+
+/// How deeply macros and includes may nest, which also stops recursive ones.
+const MAX_DEPTH: usize = 32;
+
+/// Where a line came from, for error messages: the line of the source being assembled, and
+/// which include or macro it was expanded from.
+#[derive(Clone, Debug)]
+struct Origin {
+    line: usize,
+    context: String,
+}
+
+impl Origin {
+    fn error(&self, message: String) -> AsmError {
+        AsmError {
+            line: self.line,
+            message: format!("{}{}", self.context, message),
+        }
+    }
+}
+
+struct Macro {
+    parameters: Vec<String>,
+    body: String,
+}
+
+/// Strips a comment, leaving semicolons inside string literals alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+fn parse_string(text: &str) -> Result<String, String> {
+    let inner = text
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a string in quotes, found `{}`", text.trim()))?;
+    let mut string = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+
+        string.push(match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '"')) => c,
+            Some(c) => return Err(format!("unknown escape `\\{}`", c)),
+            None => return Err("string ends in a backslash".to_string()),
+        });
+    }
+
+    Ok(string)
+}
+
+/// Splits a line into its first word and the rest.
+fn split_word(text: &str) -> (&str, &str) {
+    match text.find(char::is_whitespace) {
+        Some(split) => (&text[..split], text[split..].trim()),
+        None => (text, ""),
+    }
+}
+
+/// Expands macros, includes, strings and pseudo-instructions into plain lines.
+struct Preprocessor<'a> {
+    include: &'a mut dyn FnMut(&str) -> Result<String, String>,
+    macros: HashMap<String, Macro>,
+    expansions: usize,
+    lines: Vec<(Origin, String)>,
+}
+
+impl Preprocessor<'_> {
+    fn emit(&mut self, origin: &Origin, text: String) {
+        self.lines.push((origin.clone(), text));
+    }
+
+    fn process(
+        &mut self,
+        source: &str,
+        origin: &dyn Fn(usize) -> Origin,
+        depth: usize,
+    ) -> Result<(), AsmError> {
+        if depth > MAX_DEPTH {
+            return Err(origin(1).error("macros or includes nest too deeply".to_string()));
+        }
+
+        let mut lines = source.lines().enumerate();
+
+        while let Some((number, line)) = lines.next() {
+            let at = origin(number + 1);
+            let mut text = strip_comment(line).trim();
+
+            // Labels go on lines of their own, so they mark the first line of an expansion.
+            while let Some(split) = text.find(':') {
+                let name = text[..split].trim();
+
+                if name.parse::<usize>().is_err() && !is_identifier(name) {
+                    break;
+                }
+
+                if name.starts_with(GENERATED) {
+                    return Err(at.error(format!("label `{}` is reserved", name)));
+                }
+
+                self.emit(&at, format!("{}:", name));
+                text = text[split + 1..].trim();
+            }
+
+            let (word, rest) = split_word(text);
+            let operand = rest.strip_prefix("->").unwrap_or(rest).trim();
+            let expect = |count: usize| -> Result<(), AsmError> {
+                let found = if operand.is_empty() { 0 } else { 1 };
+
+                if found != count || operand.contains(',') {
+                    return Err(at.error(format!("`{}` takes {} parameters", word, count)));
+                }
+
+                Ok(())
+            };
+
+            match word {
+                "" => {}
+                ".macro" => {
+                    let (name, parameters) = split_word(rest);
+
+                    if !is_identifier(name)
+                        || OpCode::from_mnemonic(name).is_some()
+                        || RESERVED.contains(&name.to_lowercase().as_str())
+                    {
+                        return Err(at.error(format!("invalid macro name `{}`", name)));
+                    }
+
+                    let parameters: Vec<String> = parameters
+                        .split(',')
+                        .map(|parameter| parameter.trim().to_string())
+                        .filter(|parameter| !parameter.is_empty())
+                        .collect();
+
+                    if let Some(bad) = parameters.iter().find(|p| !is_identifier(p)) {
+                        return Err(at.error(format!("invalid macro parameter `{}`", bad)));
+                    }
+
+                    let mut body = String::new();
+
+                    loop {
+                        match lines.next() {
+                            Some((_, line)) if strip_comment(line).trim() == ".endm" => break,
+                            Some((_, line)) => {
+                                body.push_str(line);
+                                body.push('\n');
+                            }
+                            None => return Err(at.error(format!("macro `{}` has no .endm", name))),
+                        }
+                    }
+
+                    self.macros
+                        .insert(name.to_string(), Macro { parameters, body });
+                }
+                ".endm" => return Err(at.error(".endm without .macro".to_string())),
+                ".include" => {
+                    let name = parse_string(rest).map_err(|e| at.error(e))?;
+                    let source = match name.as_str() {
+                        "std" => STD.to_string(),
+                        _ => (self.include)(&name).map_err(|e| at.error(e))?,
+                    };
+                    let nested = |line| Origin {
+                        line: at.line,
+                        context: format!("{}in `{}` line {}: ", at.context, name, line),
+                    };
+
+                    self.process(&source, &nested, depth + 1)?;
+                }
+                ".ascii" | ".asciz" => {
+                    let mut codes: Vec<String> = parse_string(rest)
+                        .map_err(|e| at.error(e))?
+                        .chars()
+                        .map(|c| (c as u32).to_string())
+                        .collect();
+
+                    if word == ".asciz" {
+                        codes.push("0".to_string());
+                    }
+
+                    if !codes.is_empty() {
+                        self.emit(&at, format!(".data {}", codes.join(", ")));
+                    }
+                }
+                "push" => {
+                    expect(1)?;
+                    self.emit(&at, format!("add {}, #0 -> [r0]", operand));
+                    self.emit(&at, "arb #1".to_string());
+                }
+                "pop" => {
+                    expect(1)?;
+                    self.emit(&at, format!("add [r-1], #0 -> {}", operand));
+                    self.emit(&at, "arb #-1".to_string());
+                }
+                "call" => {
+                    expect(1)?;
+                    self.expansions += 1;
+
+                    let label = format!("{}return{}", GENERATED, self.expansions);
+                    self.emit(&at, format!("add #{}, #0 -> [r0]", label));
+                    self.emit(&at, "arb #1".to_string());
+                    self.emit(&at, format!("jt #1, {}", operand));
+                    self.emit(&at, format!("{}:", label));
+                }
+                "ret" => {
+                    expect(0)?;
+                    self.emit(&at, "arb #-1".to_string());
+                    self.emit(&at, "jt #1, [r0]".to_string());
+                }
+                "drop" => {
+                    expect(1)?;
+                    let count: i64 = operand.parse().map_err(|_| {
+                        at.error(format!("`drop` needs a count, found `{}`", operand))
+                    })?;
+                    self.emit(&at, format!("arb #{}", -count));
+                }
+                _ if self.macros.contains_key(word) => {
+                    let definition = &self.macros[word];
+                    let arguments: Vec<&str> = match rest {
+                        "" => Vec::new(),
+                        _ => rest.split(',').map(str::trim).collect(),
+                    };
+
+                    if arguments.len() != definition.parameters.len() {
+                        return Err(at.error(format!(
+                            "macro `{}` takes {} parameters, found {}",
+                            word,
+                            definition.parameters.len(),
+                            arguments.len()
+                        )));
+                    }
+
+                    // Longer names first, so `%ab` is not taken for `%a` followed by `b`.
+                    let mut substitutions: Vec<(&String, &str)> =
+                        definition.parameters.iter().zip(arguments).collect();
+                    substitutions.sort_by_key(|(parameter, _)| std::cmp::Reverse(parameter.len()));
+
+                    self.expansions += 1;
+
+                    let mut body = definition.body.replace("%@", &self.expansions.to_string());
+
+                    for (parameter, argument) in substitutions {
+                        body = body.replace(&format!("%{}", parameter), argument);
+                    }
+
+                    let name = word.to_string();
+                    let nested = |line| Origin {
+                        line: at.line,
+                        context: format!("{}in macro `{}` line {}: ", at.context, name, line),
+                    };
+
+                    self.process(&body, &nested, depth + 1)?;
+                }
+                _ => self.emit(&at, text.to_string()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn resolve(value: &Value, labels: &HashMap<String, usize>) -> Result<i64, String> {
+    match value {
+        Value::Number(number) => Ok(*number),
+        Value::Label(label, offset) => labels
+            .get(label)
+            .map(|&address| address as i64 + offset)
+            .ok_or_else(|| format!("undefined label `{}`", label)),
+    }
+}
+
+/// Assembles `source` into an Intcode image. Only the standard library can be included.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    assemble_with(source, |name| Err(format!("cannot include `{}`", name)))
+}
+
+/// Like [`assemble`], calling `include` for the source of each `.include` other than `std`.
+pub fn assemble_with(
+    source: &str,
+    mut include: impl FnMut(&str) -> Result<String, String>,
+) -> Result<Vec<i64>, AsmError> {
+    let mut preprocessor = Preprocessor {
+        include: &mut include,
+        macros: HashMap::new(),
+        expansions: 0,
+        lines: Vec::new(),
+    };
+    let top = |line| Origin {
+        line,
+        context: String::new(),
+    };
+    preprocessor.process(source, &top, 0)?;
+
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    let mut items: Vec<(Origin, Item)> = Vec::new();
+    let mut address = 0;
+
+    for (origin, line) in preprocessor.lines {
+        let error = |message: String| origin.error(message);
+        let mut text = line.trim();
+
+        while let Some(split) = text.find(':') {
+            let name = text[..split].trim();
+
+            if let Ok(expected) = name.parse::<usize>() {
+                if expected != address {
+                    return Err(error(format!(
+                        "line is marked {:04} but assembles at {:04}",
+                        expected, address
+                    )));
+                }
+            } else if is_identifier(name) {
+                if labels.insert(name.to_string(), address).is_some() {
+                    return Err(error(format!("label `{}` defined twice", name)));
+                }
+            } else {
+                break;
+            }
+
+            text = text[split + 1..].trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let item = parse_item(text).map_err(error)?;
+        address += item.size();
+        items.push((origin, item));
+    }
+
+    let mut image = Vec::with_capacity(address);
+
+    for (origin, item) in items {
+        let error = |message: String| origin.error(message);
+
+        match item {
+            Item::Instruction(opcode, operands) => {
+                let modes = operands
+                    .iter()
+                    .rev()
+                    .fold(0, |modes, operand| modes * 10 + operand.mode as i64);
+                image.push(modes * 100 + opcode as i64);
+
+                for operand in operands {
+                    image.push(resolve(&operand.value, &labels).map_err(error)?);
+                }
+            }
+            Item::Data(values) => {
+                for value in values {
+                    image.push(resolve(&value, &labels).map_err(error)?);
+                }
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, assemble_with, AsmError};
+    use crate::disasm::disassemble;
+    use crate::examples::EXAMPLES;
+    use crate::machine::{Machine, State};
+    use crate::terminal::QueueTerminal;
+
+    /// Runs `source` on `inputs` until it halts or runs out of input, returning its output as
+    /// text.
+    fn run_text(source: &str, inputs: &[i64]) -> String {
+        let program = assemble(source).unwrap();
+        let mut term = QueueTerminal::new(inputs);
+        Machine::new(&program).resume(&mut term).unwrap();
+        term.outputs.iter().map(|&c| c as u8 as char).collect()
+    }
+
+    #[test]
+    fn echo_until_zero() {
+        let source = "
+            ; Echo numbers until a zero is read.
+            loop:   in -> [value]
+                    jf [value], #done
+                    out [value]
+                    jt #1, #loop
+            done:   hlt
+            value:  .data 0
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!(program, [3, 11, 1006, 11, 10, 4, 11, 1105, 1, 0, 99, 0]);
+
+        let mut term = QueueTerminal::new(&[4, 5, 6, 0]);
+        Machine::new(&program).execute(&mut term).unwrap();
+        assert_eq!(term.outputs, [4, 5, 6]);
+    }
+
+    #[test]
+    fn operand_modes() {
+        assert_eq!(
+            assemble("ADD [r26], #-4 -> [26]\narb [r+x]\nx: mul [r-1], [x+1], [r0]").unwrap(),
+            [1201, 26, -4, 26, 209, 6, 20202, -1, 7, 0]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |line, message: &str| {
+            Err(AsmError {
+                line,
+                message: message.to_string(),
+            })
+        };
+
+        assert_eq!(assemble("nop"), error(1, "unknown mnemonic `nop`"));
+        assert_eq!(
+            assemble("\nadd #1, #2"),
+            error(2, "`add` takes 3 parameters, found 2")
+        );
+        assert_eq!(
+            assemble("in -> #4"),
+            error(1, "`in` cannot write to an immediate parameter")
+        );
+        assert_eq!(
+            assemble("out 4"),
+            error(1, "operand `4` needs a mode: #x, [x] or [rx]")
+        );
+        assert_eq!(
+            assemble("jt #1, #nowhere"),
+            error(1, "undefined label `nowhere`")
+        );
+        assert_eq!(
+            assemble("a: hlt\na: hlt"),
+            error(2, "label `a` defined twice")
+        );
+        assert_eq!(
+            assemble("0000: hlt\n0002: hlt"),
+            error(2, "line is marked 0002 but assembles at 0001")
+        );
+        assert_eq!(
+            assemble("push #1, #2"),
+            error(1, "`push` takes 1 parameters")
+        );
+        assert_eq!(
+            assemble(".macro m a\nout %a\n.endm\nm"),
+            error(4, "macro `m` takes 1 parameters, found 0")
+        );
+        assert_eq!(
+            assemble(".macro m\nout %a\n.endm\n\nm"),
+            error(
+                5,
+                "in macro `m` line 1: operand `%a` needs a mode: #x, [x] or [rx]"
+            )
+        );
+        assert_eq!(
+            assemble(".macro m\nhlt"),
+            error(1, "macro `m` has no .endm")
+        );
+        for name in &["add", "push", "Call", "ret", "drop", ".data", ".endm"] {
+            assert_eq!(
+                assemble(&format!(".macro {} x\nout %x\n.endm", name)),
+                error(1, &format!("invalid macro name `{}`", name))
+            );
+        }
+        assert_eq!(assemble(".ascii \"\\q\""), error(1, "unknown escape `\\q`"));
+        assert_eq!(
+            assemble("arb #stack\ncall #f\n__return1: hlt\nf: ret\nstack:"),
+            error(3, "label `__return1` is reserved")
+        );
+    }
+
+    #[test]
+    fn stack() {
+        let source = "
+                    arb #stack
+            next:   in -> [x]
+                    push [x]
+                    call #double
+                    pop -> [x]
+                    out [x]
+                    jt #1, #next
+            double: mul [r-2], #2 -> [r-2]
+                    ret
+            x:      .data 0
+            stack:
+        ";
+        let program = assemble(source).unwrap();
+        let mut term = QueueTerminal::new(&[4, -21]);
+        assert_eq!(
+            Machine::new(&program).resume(&mut term),
+            Ok(State::NeedsInput)
+        );
+        assert_eq!(term.outputs, [8, -42]);
+
+        assert_eq!(
+            assemble("push #1\npop -> [r2]\ndrop 2\nret").unwrap(),
+            [21101, 1, 0, 0, 109, 1, 21201, -1, 0, 2, 109, -1, 109, -2, 109, -1, 2105, 1, 0]
+        );
+    }
+
+    #[test]
+    fn macros_and_strings() {
+        let source = r#"
+            .macro countdown from
+                    add #%from, #0 -> [count]
+            loop%@: out [count]
+                    add [count], #-1 -> [count]
+                    jt [count], #loop%@
+            .endm
+
+                    countdown 3
+                    countdown 2
+                    add #text, #0 -> [next+1]
+            next:   out [0]             ; prints one character; "quotes" are fine here
+                    hlt
+            count:  .data 0
+            text:   .asciz "a;\"\n"
+        "#;
+        let program = assemble(source).unwrap();
+        assert_eq!(&program[program.len() - 5..], [97, 59, 34, 10, 0]);
+
+        let mut term = QueueTerminal::default();
+        Machine::new(&program).execute(&mut term).unwrap();
+        assert_eq!(term.outputs, [3, 2, 1, 2, 1, 97]);
+    }
+
+    #[test]
+    fn standard_library() {
+        let print_numbers = "
+                    arb #stack
+            next:   in -> [n]
+                    push [n]
+                    call #print_number
+                    drop 1
+                    out #10
+                    jt #1, #next
+            n:      .data 0
+                    .include \"std\"
+            stack:
+        ";
+        assert_eq!(
+            run_text(print_numbers, &[0, 7, 10, 1234567, -42, i64::MAX, i64::MIN]),
+            "0\n7\n10\n1234567\n-42\n9223372036854775807\n-9223372036854775808\n"
+        );
+
+        let greet = r#"
+                    arb #stack
+                    push #stack+1000
+                    call #read_line
+                    pop -> [length]
+                    push #hello
+                    call #print_string
+                    push #stack+1000
+                    call #print_string
+                    drop 2
+                    out #10
+                    push [length]
+                    call #print_number
+                    hlt
+            length: .data 0
+            hello:  .asciz "Hello, "
+                    .include "std"
+            stack:
+        "#;
+        let name: Vec<i64> = "Intcode\nignored".bytes().map(i64::from).collect();
+        assert_eq!(run_text(greet, &name), "Hello, Intcode\n7");
+
+        let loops = "
+                    arb #stack
+                    push #3
+                    push #4
+                    call #power
+                    pop -> [result]
+                    drop 1
+                    out [result]
+                    push #10
+                    call #factorial
+                    pop -> [result]
+                    out [result]
+                    hlt
+            result: .data 0
+                    .include \"std\"
+            stack:
+        ";
+        let mut term = QueueTerminal::default();
+        Machine::new(&assemble(loops).unwrap())
+            .execute(&mut term)
+            .unwrap();
+        assert_eq!(term.outputs, [81, 3628800]);
+    }
+
+    #[test]
+    fn includes() {
+        let include = |name: &str| match name {
+            "double" => Ok(".macro double x\nadd %x, %x -> %x\n.endm".to_string()),
+            "bad" => Ok("hlt\nnop".to_string()),
+            "loop" => Ok(".include \"loop\"".to_string()),
+            _ => Err(format!("no file `{}`", name)),
+        };
+
+        assert_eq!(
+            assemble_with(".include \"double\"\ndouble [r1]", include),
+            Ok(vec![22201, 1, 1, 1])
+        );
+        assert_eq!(
+            assemble_with("hlt\n.include \"bad\"", include),
+            Err(AsmError {
+                line: 2,
+                message: "in `bad` line 2: unknown mnemonic `nop`".to_string()
+            })
+        );
+        assert_eq!(
+            assemble_with(".include \"loop\"", include)
+                .map_err(|e| e.message.ends_with("macros or includes nest too deeply")),
+            Err(true)
+        );
+        assert_eq!(
+            assemble_with(".include \"missing\"", include),
+            Err(AsmError {
+                line: 1,
+                message: "no file `missing`".to_string()
+            })
+        );
+        assert_eq!(
+            assemble(".include \"double\""),
+            Err(AsmError {
+                line: 1,
+                message: "cannot include `double`".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn disassembly_round_trip() {
+        for program in EXAMPLES {
+            let listing = disassemble(program).to_string();
+            assert_eq!(assemble(&listing).as_deref(), Ok(*program), "\n{}", listing);
+        }
+    }
+}
@@ -0,0 +1,598 @@
+//! Ahead-of-time compilation of Intcode programs to Rust.
+//!
+//! [`to_rust`] turns a program image into Rust source for a type implementing [`Native`], meant
+//! to be written by a build script and `include!`d by the crate that runs the program:
+//!
+//! ```ignore
+//! // build.rs
+//! let source = intcode::compile::to_rust(&program, "Amplifier");
+//! fs::write(Path::new(&env::var("OUT_DIR")?).join("amplifier.rs"), source)?;
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/amplifier.rs"));
+//! let mut amplifier = Compiled::new(Amplifier);
+//! ```
+//!
+//! Every instruction reachable from address 0 through fall-through and immediate jumps is
+//! compiled, with parameter modes resolved at compile time, into straight-line basic blocks.
+//! [`Compiled`] runs that code and hands over to the interpreter whenever the compiled code
+//! cannot go on: for good once a write lands on compiled code, and for a single instruction
+//! when execution reaches an address that was not compiled.
+
+use crate::disasm::reachable;
+use crate::error::MachineError;
+use crate::instruction::Instruction;
+use crate::machine::{Machine, State, MEMORY_LIMIT};
+use crate::opcode::{OpCode, ParameterMode};
+use crate::terminal::Terminal;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// The registers of a machine, lent to compiled code by [`Machine::run_native`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Registers {
+    pub pc: usize,
+    pub relative_base: i64,
+    pub steps: u64,
+}
+
+/// Why compiled code gave control back.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Exit {
+    /// The machine halted or needs input, just as the interpreter would have stopped.
+    State(State),
+    /// An instruction wrote to compiled code, so only the interpreter is right from here on.
+    SelfModified,
+    /// The pc is not the start of a compiled block.
+    Uncompiled,
+}
+
+/// A program compiled by [`to_rust`].
+pub trait Native {
+    /// The image the code was compiled from.
+    const PROGRAM: &'static [i64];
+
+    /// Runs from `registers.pc` until the machine stops or the compiled code cannot go on.
+    fn run(
+        &self,
+        memory: &mut Vec<i64>,
+        registers: &mut Registers,
+        term: &mut impl Terminal,
+    ) -> Result<Exit, MachineError>;
+}
+
+pub fn read(memory: &[i64], address: usize) -> i64 {
+    memory.get(address).cloned().unwrap_or(0)
+}
+
+/// Fails like the interpreter does if writing to `address` would grow memory past
+/// [`MEMORY_LIMIT`].
+pub fn writable(pc: usize, memory: &[i64], address: usize) -> Result<usize, MachineError> {
+    if address < memory.len().max(MEMORY_LIMIT) {
+        Ok(address)
+    } else {
+        Err(MachineError::MemoryLimitExceeded { pc, address })
+    }
+}
+
+/// Writes like the interpreter does, failing rather than growing memory past [`MEMORY_LIMIT`].
+pub fn write(
+    pc: usize,
+    memory: &mut Vec<i64>,
+    address: usize,
+    value: i64,
+) -> Result<(), MachineError> {
+    if address >= memory.len() {
+        writable(pc, memory, address)?;
+        memory.resize(address + 1, 0);
+    }
+
+    memory[address] = value;
+    Ok(())
+}
+
+/// Turns a computed value into an address like the interpreter does.
+pub fn address(pc: usize, value: i64) -> Result<usize, MachineError> {
+    if value < 0 {
+        Err(MachineError::NegativeAddress { pc, address: value })
+    } else {
+        Ok(value as usize)
+    }
+}
+
+/// Adds like the interpreter does, failing on overflow.
+pub fn sum(pc: usize, a: i64, b: i64) -> Result<i64, MachineError> {
+    a.checked_add(b).ok_or(MachineError::ValueOutOfRange { pc })
+}
+
+/// Multiplies like the interpreter does, failing on overflow.
+pub fn product(pc: usize, a: i64, b: i64) -> Result<i64, MachineError> {
+    a.checked_mul(b).ok_or(MachineError::ValueOutOfRange { pc })
+}
+
+/// A machine running compiled code, falling back to the interpreter where it has to.
+///
+/// Compiled code does not count against a step limit, watch the clock or look for loops, so a
+/// machine with any of those set is always interpreted.
+pub struct Compiled<N> {
+    pub machine: Machine,
+    native: N,
+    modified: bool,
+}
+
+impl<N: Native> Compiled<N> {
+    pub fn new(native: N) -> Compiled<N> {
+        Compiled {
+            machine: Machine::new(N::PROGRAM),
+            native,
+            modified: false,
+        }
+    }
+
+    /// Whether the program has written to its own code, leaving the rest to the interpreter.
+    pub fn self_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Like [`Machine::resume`].
+    pub fn resume(&mut self, term: &mut impl Terminal) -> Result<State, MachineError> {
+        resume(&self.native, &mut self.machine, &mut self.modified, term)
+    }
+
+    /// Like [`Machine::execute`].
+    pub fn execute(&mut self, term: &mut impl Terminal) -> Result<(), MachineError> {
+        match self.resume(term)? {
+            State::NeedsInput => Err(MachineError::InputExhausted {
+                pc: self.machine.pc(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Resumes `machine`, which must have been loaded with `N::PROGRAM`, in `native`'s code as
+/// far as it can. `modified` records that the program wrote to its code, for the next call.
+pub(crate) fn resume<N: Native>(
+    native: &N,
+    machine: &mut Machine,
+    modified: &mut bool,
+    term: &mut impl Terminal,
+) -> Result<State, MachineError> {
+    loop {
+        if *modified || machine.guarded() {
+            return machine.resume(term);
+        }
+
+        let exit = machine.run_native(|memory, registers| native.run(memory, registers, term))?;
+
+        match exit {
+            Exit::State(state) => return Ok(state),
+            Exit::SelfModified => *modified = true,
+            Exit::Uncompiled => match machine.step(term)? {
+                State::Running => continue,
+                state => return Ok(state),
+            },
+        }
+    }
+}
+
+/// Instructions that compiled code can execute, keyed by address: everything reachable from
+/// address 0, plus whatever decodes cleanly in the gaps, since dynamic jumps can land there.
+///
+/// Instructions with a negative position operand would fail in the interpreter and are left
+/// to it, so the error comes out the same.
+fn compilable(program: &[i64]) -> BTreeMap<usize, Instruction> {
+    let mut instructions: BTreeMap<usize, Instruction> = reachable(program).into_iter().collect();
+    let mut covered = vec![false; program.len()];
+
+    for (&address, instruction) in &instructions {
+        let end = (address + instruction.size()).min(program.len());
+        covered[address..end]
+            .iter_mut()
+            .for_each(|cell| *cell = true);
+    }
+
+    let mut address = 0;
+    while address < program.len() {
+        match Instruction::decode(program, address) {
+            Some(instruction)
+                if address + instruction.size() <= program.len()
+                    && !covered[address..address + instruction.size()].contains(&true) =>
+            {
+                address += instruction.size();
+                instructions.insert(instruction.address, instruction);
+            }
+            _ => address += 1,
+        }
+    }
+
+    instructions
+        .into_iter()
+        .filter(|(_, instruction)| {
+            instruction
+                .parameters
+                .iter()
+                .all(|p| p.mode != ParameterMode::Position || p.value >= 0)
+        })
+        .collect()
+}
+
+/// Merges addresses into inclusive ranges.
+fn ranges(addresses: impl IntoIterator<Item = usize>) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+    for address in addresses {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == address => *last = address,
+            _ => ranges.push((address, address)),
+        }
+    }
+
+    ranges
+}
+
+struct Compiler {
+    instructions: BTreeMap<usize, Instruction>,
+    /// Where basic blocks start: the entry point, jump targets, the instruction after a
+    /// conditional jump, every input instruction so a paused machine can resume there, and
+    /// every instruction no other compiled instruction falls through to.
+    leaders: BTreeSet<usize>,
+    /// Operand cells that compiled instructions write to with a fixed target. Their values are
+    /// read from memory when the instruction runs instead of being compiled in.
+    variable: BTreeSet<usize>,
+    /// Cells whose values are compiled in: a write to any of them leaves compiled code.
+    fixed: Vec<(usize, usize)>,
+}
+
+impl Compiler {
+    fn new(program: &[i64]) -> Compiler {
+        let instructions = compilable(program);
+        let mut leaders = BTreeSet::new();
+        let mut variable = BTreeSet::new();
+        let mut fall_through = BTreeSet::new();
+
+        leaders.insert(0);
+
+        for (&address, instruction) in &instructions {
+            let next = address + instruction.size();
+
+            match instruction.opcode {
+                OpCode::JumpIfTrue | OpCode::JumpIfNot => {
+                    leaders.extend(instruction.jump_target());
+                    leaders.insert(next);
+                }
+                OpCode::Input => {
+                    leaders.insert(address);
+                }
+                _ => {}
+            }
+
+            if instruction.falls_through() {
+                fall_through.insert(next);
+            }
+
+            if let (Some(_), Some(parameter)) =
+                (instruction.opcode.target(), instruction.parameters.last())
+            {
+                if parameter.mode == ParameterMode::Position {
+                    variable.insert(parameter.value as usize);
+                }
+            }
+        }
+
+        leaders.extend(
+            instructions
+                .keys()
+                .filter(|address| !fall_through.contains(address)),
+        );
+        leaders.retain(|address| instructions.contains_key(address));
+        variable.retain(|&cell| {
+            instructions
+                .range(..cell)
+                .next_back()
+                .is_some_and(|(&address, instruction)| {
+                    address < cell && cell < address + instruction.size()
+                })
+        });
+
+        let fixed = ranges(instructions.iter().flat_map(|(&address, instruction)| {
+            (address..address + instruction.size()).filter(|cell| !variable.contains(cell))
+        }));
+
+        Compiler {
+            instructions,
+            leaders,
+            variable,
+            fixed,
+        }
+    }
+
+    /// The value of the `position`th parameter as written in the instruction.
+    fn value(&self, instruction: &Instruction, position: usize) -> String {
+        let cell = instruction.address + position;
+
+        if self.variable.contains(&cell) {
+            format!("intcode::compile::read(memory, {})", cell)
+        } else {
+            format!("{}i64", instruction.parameters[position - 1].value)
+        }
+    }
+
+    fn operand(&self, instruction: &Instruction, position: usize) -> String {
+        match instruction.parameters[position - 1].mode {
+            ParameterMode::Immediate => self.value(instruction, position),
+            _ => format!(
+                "intcode::compile::read(memory, {})",
+                self.target(instruction, position)
+            ),
+        }
+    }
+
+    fn target(&self, instruction: &Instruction, position: usize) -> String {
+        let pc = instruction.address;
+        let value = self.value(instruction, position);
+
+        match instruction.parameters[position - 1].mode {
+            ParameterMode::Relative => format!(
+                "intcode::compile::address({}, intcode::compile::sum({}, registers.relative_base, {})?)?",
+                pc, pc, value
+            ),
+            _ if self.variable.contains(&(pc + position)) => {
+                format!("intcode::compile::address({}, {})?", pc, value)
+            }
+            _ => format!("{}usize", instruction.parameters[position - 1].value),
+        }
+    }
+
+    /// Emits the write of `value` to `target`, the address of the instruction's last
+    /// parameter, leaving compiled code when the write lands on a fixed cell.
+    fn store(&self, instruction: &Instruction, value: &str, out: &mut String) {
+        let position = instruction.parameters.len();
+        let parameter = &instruction.parameters[position - 1];
+        let modified = format!(
+            "registers.pc = {}; registers.steps += 1; return Ok(intcode::compile::Exit::SelfModified);",
+            instruction.address + instruction.size()
+        );
+
+        writeln!(
+            out,
+            "intcode::compile::write({}, memory, target, {})?;",
+            instruction.address, value
+        )
+        .unwrap();
+
+        if parameter.mode == ParameterMode::Position
+            && !self.variable.contains(&(instruction.address + position))
+        {
+            let cell = parameter.value as usize;
+
+            if self
+                .fixed
+                .iter()
+                .any(|&(start, end)| start <= cell && cell <= end)
+            {
+                writeln!(out, "{}", modified).unwrap();
+            }
+        } else if !self.fixed.is_empty() {
+            let checks: Vec<String> = self
+                .fixed
+                .iter()
+                .map(|(start, end)| format!("{}..={}", start, end))
+                .collect();
+            writeln!(
+                out,
+                "if let {} = target {{ {} }}",
+                checks.join(" | "),
+                modified
+            )
+            .unwrap();
+        }
+    }
+
+    fn emit(&self, instruction: &Instruction, out: &mut String) {
+        let pc = instruction.address;
+
+        writeln!(out, "// {:04}: {}", pc, instruction).unwrap();
+        writeln!(out, "registers.pc = {};", pc).unwrap();
+
+        match instruction.opcode {
+            OpCode::Add | OpCode::Multiply | OpCode::LessThan | OpCode::Equals => {
+                writeln!(out, "let r1 = {};", self.operand(instruction, 1)).unwrap();
+                writeln!(out, "let r2 = {};", self.operand(instruction, 2)).unwrap();
+
+                // The interpreter computes the value before the address it goes to, so an
+                // overflow is reported ahead of a bad target.
+                match instruction.opcode {
+                    OpCode::Add => {
+                        writeln!(out, "let value = intcode::compile::sum({}, r1, r2)?;", pc)
+                    }
+                    OpCode::Multiply => {
+                        writeln!(
+                            out,
+                            "let value = intcode::compile::product({}, r1, r2)?;",
+                            pc
+                        )
+                    }
+                    OpCode::LessThan => writeln!(out, "let value = (r1 < r2) as i64;"),
+                    _ => writeln!(out, "let value = (r1 == r2) as i64;"),
+                }
+                .unwrap();
+                writeln!(out, "let target = {};", self.target(instruction, 3)).unwrap();
+                self.store(instruction, "value", out);
+            }
+            OpCode::Input => {
+                // The interpreter checks the target before taking input, so a bad target does
+                // not use up a value.
+                writeln!(
+                    out,
+                    "let target = intcode::compile::writable({}, memory, {})?;",
+                    pc,
+                    self.target(instruction, 1)
+                )
+                .unwrap();
+                writeln!(out, "let value = match term.input() {{ Some(value) => value, None => return Ok(intcode::compile::Exit::State(intcode::State::NeedsInput)) }};").unwrap();
+                self.store(instruction, "value", out);
+            }
+            OpCode::Output => {
+                writeln!(out, "term.output({});", self.operand(instruction, 1)).unwrap();
+            }
+            OpCode::JumpIfTrue | OpCode::JumpIfNot => {
+                let comparison = match instruction.opcode {
+                    OpCode::JumpIfTrue => "!=",
+                    _ => "==",
+                };
+
+                writeln!(out, "let r1 = {};", self.operand(instruction, 1)).unwrap();
+                writeln!(out, "let r2 = {};", self.operand(instruction, 2)).unwrap();
+                writeln!(
+                    out,
+                    "if r1 {} 0 {{ registers.pc = intcode::compile::address({}, r2)?; registers.steps += 1; continue; }}",
+                    comparison, pc
+                )
+                .unwrap();
+            }
+            OpCode::AdjustRelativeBase => {
+                writeln!(
+                    out,
+                    "registers.relative_base = intcode::compile::sum({}, registers.relative_base, {})?;",
+                    pc,
+                    self.operand(instruction, 1)
+                )
+                .unwrap();
+            }
+            OpCode::End => {
+                writeln!(
+                    out,
+                    "return Ok(intcode::compile::Exit::State(intcode::State::Halted));"
+                )
+                .unwrap();
+                return;
+            }
+        }
+
+        writeln!(out, "registers.steps += 1;").unwrap();
+    }
+
+    fn block(&self, leader: usize, out: &mut String) {
+        let mut address = leader;
+
+        loop {
+            let instruction = &self.instructions[&address];
+            self.emit(instruction, out);
+
+            if instruction.opcode == OpCode::End {
+                break;
+            }
+
+            address += instruction.size();
+
+            if !self.instructions.contains_key(&address) {
+                writeln!(out, "registers.pc = {};", address).unwrap();
+                writeln!(out, "return Ok(intcode::compile::Exit::Uncompiled);").unwrap();
+                break;
+            }
+
+            if self.leaders.contains(&address) {
+                writeln!(out, "registers.pc = {};", address).unwrap();
+                break;
+            }
+        }
+    }
+}
+
+/// Compiles `program` into Rust source defining a unit struct `name` that implements
+/// [`Native`]. The source refers to this crate as `intcode`.
+pub fn to_rust(program: &[i64], name: &str) -> String {
+    let compiler = Compiler::new(program);
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "/// Compiled from an Intcode image by `intcode::compile`."
+    )
+    .unwrap();
+    writeln!(out, "pub struct {};", name).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "#[allow(clippy::all, unused, unreachable_code)]").unwrap();
+    writeln!(out, "impl intcode::compile::Native for {} {{", name).unwrap();
+    writeln!(out, "const PROGRAM: &'static [i64] = &{:?};", program).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "fn run(&self, memory: &mut Vec<i64>, registers: &mut intcode::compile::Registers, term: &mut impl intcode::Terminal) -> Result<intcode::compile::Exit, intcode::MachineError> {{").unwrap();
+    writeln!(out, "loop {{").unwrap();
+    writeln!(out, "match registers.pc {{").unwrap();
+
+    for &leader in &compiler.leaders {
+        writeln!(out, "{} => {{", leader).unwrap();
+        compiler.block(leader, &mut out);
+        writeln!(out, "}}").unwrap();
+    }
+
+    writeln!(out, "_ => return Ok(intcode::compile::Exit::Uncompiled),").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_rust, Compiler};
+    use crate::examples::COUNTDOWN;
+
+    #[test]
+    fn blocks() {
+        let compiler = Compiler::new(COUNTDOWN);
+
+        assert_eq!(
+            compiler.instructions.keys().cloned().collect::<Vec<_>>(),
+            [0, 2, 4, 8, 11]
+        );
+        assert_eq!(
+            compiler.leaders.iter().cloned().collect::<Vec<_>>(),
+            [0, 2, 11]
+        );
+        assert!(compiler.variable.is_empty());
+        assert_eq!(compiler.fixed, [(0, 11)]);
+
+        let source = to_rust(COUNTDOWN, "Countdown");
+        assert!(source.contains("pub struct Countdown;"));
+        assert!(source.contains("// 0004: ADD [12], #-1 -> [12]"));
+        assert!(!source.contains("SelfModified"));
+    }
+
+    #[test]
+    fn input_target() {
+        // Reads into a cell past the memory limit, which must fail before input is taken.
+        let source = to_rust(&[3, 1 << 40, 99], "Overflow");
+        let check = source
+            .find("intcode::compile::writable(0, memory, 1099511627776usize)?")
+            .expect("No target check");
+        assert!(check < source.find("term.input()").expect("No input"));
+    }
+
+    #[test]
+    fn self_modification() {
+        // Jumps to wherever its input says, reached only through the patched operand.
+        let program = [3, 4, 1105, 1, 0, 99, 104, 7, 99];
+        let compiler = Compiler::new(&program);
+
+        assert_eq!(
+            compiler.leaders.iter().cloned().collect::<Vec<_>>(),
+            [0, 5, 6]
+        );
+        assert_eq!(compiler.variable.iter().cloned().collect::<Vec<_>>(), [4]);
+        assert_eq!(compiler.fixed, [(0, 3), (5, 8)]);
+
+        let source = to_rust(&program, "Dispatch");
+        assert!(source.contains("let r2 = intcode::compile::read(memory, 4);"));
+        assert!(!source.contains("SelfModified"));
+
+        // Turns its second instruction into a halt.
+        let program = [1101, 98, 1, 4, 1, 0, 0, 0];
+        let source = to_rust(&program, "Patch");
+        assert!(source.contains(
+            "registers.pc = 4; registers.steps += 1; return Ok(intcode::compile::Exit::SelfModified);"
+        ));
+    }
+}
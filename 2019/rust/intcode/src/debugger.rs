@@ -0,0 +1,618 @@
+//! Interactive step debugger.
+//!
+//! The debugger drives a [`Machine`] one instruction at a time through a [`QueueTerminal`], so
+//! program input is whatever has been queued with the `input` command and program output is
+//! echoed after every command. Commands are read line by line from any `BufRead`, which lets
+//! tests script a whole session.
+//!
+//! The machine keeps an undo log of recent instructions, so execution can also run backwards
+//! to find the instruction that wrote a bad value. Input taken back this way is queued again,
+//! but output already shown stays shown.
+
+use crate::error::MachineError;
+use crate::instruction::Instruction;
+use crate::machine::{Machine, MemoryWrite, Snapshot, State};
+use crate::terminal::QueueTerminal;
+use crate::watch::{self, Access, Action, Watch, Watcher};
+use std::collections::{BTreeSet, VecDeque};
+use std::io::{self, BufRead, Write};
+
+/// Instructions the undo log reaches back.
+const HISTORY: usize = 1_000_000;
+
+/// Most memory cells `x` shows at once.
+const MAX_CELLS: usize = 256;
+
+const HELP: &str = "\
+step [n]         s   Execute n instructions (default 1)
+continue         c   Run until a breakpoint, an input wait or the end
+reverse-step [n] rs  Undo n instructions (default 1)
+reverse-continue rc  Run backwards until a breakpoint or the start of the undo log
+break <addr>     b   Stop when pc reaches addr
+watch <addr> [m] w   Stop after an instruction accesses addr, where m is r, w (default) or rw
+log <addr> [m]       Print accesses to addr without stopping
+delete <addr>    d   Remove breakpoints and watchpoints at addr
+info             i   List breakpoints and watchpoints
+heat [width]         Show how often each memory cell has been read and written
+regs             r   Show pc, relative base and step count
+x <addr> [n]         Show n memory cells starting at addr, at most 256
+poke <addr> <v>      Set a memory cell
+save                 Remember the current machine state and input queue
+restore              Go back to the state remembered by `save`
+input <v>...     in  Queue values for input instructions
+list [n]         l   Disassemble n instructions from pc (default 5)
+help             h   Show this help
+quit             q   Leave the debugger";
+
+/// Why execution stopped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Stop {
+    /// A single step finished with nothing noteworthy.
+    Stepped,
+    /// The pc reached a breakpoint.
+    Breakpoint(usize),
+    /// An instruction wrote to a watched address.
+    WriteBreakpoint(MemoryWrite),
+    /// An instruction read a watched address.
+    ReadBreakpoint {
+        address: usize,
+        value: i64,
+    },
+    /// The program is waiting for input that has not been queued.
+    NeedsInput,
+    Halted,
+    Error(MachineError),
+    /// Running backwards used up the undo log.
+    StartOfHistory,
+}
+
+pub struct Debugger {
+    pub machine: Machine,
+    pub terminal: QueueTerminal,
+    pub watcher: Watcher,
+    breakpoints: BTreeSet<usize>,
+    saved: Option<(Snapshot, VecDeque<i64>)>,
+}
+
+impl Debugger {
+    pub fn new(program: &[i64]) -> Debugger {
+        let mut machine = Machine::new(program);
+        machine.set_history(Some(HISTORY));
+
+        Debugger {
+            machine,
+            terminal: QueueTerminal::default(),
+            watcher: Watcher::new(),
+            breakpoints: BTreeSet::new(),
+            saved: None,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn add_write_breakpoint(&mut self, address: usize) {
+        self.watcher.add(address, Watch::Write, Action::Pause);
+    }
+
+    /// Removes the breakpoint and watchpoint at `address`, returning whether there were any.
+    pub fn remove_breakpoints(&mut self, address: usize) -> bool {
+        let pc = self.breakpoints.remove(&address);
+        let watch = self.watcher.remove(address);
+        pc || watch
+    }
+
+    /// Remembers the machine state and pending input so `restore` can rewind to them.
+    pub fn save(&mut self) {
+        self.saved = Some((self.machine.snapshot(), self.terminal.inputs.clone()));
+    }
+
+    /// Rewinds to the last `save`, returning whether there was one.
+    pub fn restore(&mut self) -> bool {
+        match &self.saved {
+            Some((snapshot, inputs)) => {
+                self.machine.restore(snapshot);
+                self.terminal.inputs = inputs.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Executes one instruction.
+    pub fn step(&mut self) -> Stop {
+        match self.watcher.step(&mut self.machine, &mut self.terminal) {
+            Ok(watch::Stop::Paused(hit)) => match hit.access {
+                Access::Read => Stop::ReadBreakpoint {
+                    address: hit.address,
+                    value: hit.value,
+                },
+                Access::Write => Stop::WriteBreakpoint(
+                    self.machine
+                        .last_write()
+                        .cloned()
+                        .expect("Write hit without a write"),
+                ),
+            },
+            Ok(watch::Stop::State(State::Running)) => Stop::Stepped,
+            Ok(watch::Stop::State(State::NeedsInput)) => Stop::NeedsInput,
+            Ok(watch::Stop::State(State::Halted)) => Stop::Halted,
+            Err(error) => Stop::Error(error),
+        }
+    }
+
+    /// Undoes one instruction, stopping if a watchpoint covers what it did: a write it made or
+    /// a read it is about to make again.
+    pub fn reverse_step(&mut self) -> Stop {
+        let undo = match self.machine.unstep() {
+            Some(undo) => undo,
+            None => return Stop::StartOfHistory,
+        };
+
+        if let Some(input) = undo.input {
+            self.terminal.inputs.push_front(input);
+        }
+
+        let paused = |address| match self.watcher.watchpoint(address) {
+            Some((watch, Action::Pause)) => Some(watch),
+            _ => None,
+        };
+
+        if let Some(write) = undo.write {
+            if paused(write.address).is_some_and(|watch| watch.covers(Access::Write)) {
+                return Stop::WriteBreakpoint(write);
+            }
+        }
+
+        let reads = Instruction::fetch(self.machine.memory(), self.machine.pc())
+            .map(|instruction| watch::reads(&instruction, self.machine.relative_base()))
+            .unwrap_or_default();
+
+        for address in reads {
+            if paused(address).is_some_and(|watch| watch.covers(Access::Read)) {
+                return Stop::ReadBreakpoint {
+                    address,
+                    value: self.machine.read(address),
+                };
+            }
+        }
+
+        Stop::Stepped
+    }
+
+    /// Runs backwards until something stops the machine, never stopping where it starts.
+    pub fn reverse_cont(&mut self) -> Stop {
+        loop {
+            match self.reverse_step() {
+                Stop::Stepped if self.breakpoints.contains(&self.machine.pc()) => {
+                    break Stop::Breakpoint(self.machine.pc())
+                }
+                Stop::Stepped => continue,
+                stop => break stop,
+            }
+        }
+    }
+
+    /// Runs until something stops the machine. A breakpoint on the current pc does not stop
+    /// it again, so `continue` always makes progress.
+    pub fn cont(&mut self) -> Stop {
+        loop {
+            match self.step() {
+                Stop::Stepped if self.breakpoints.contains(&self.machine.pc()) => {
+                    break Stop::Breakpoint(self.machine.pc())
+                }
+                Stop::Stepped => continue,
+                stop => break stop,
+            }
+        }
+    }
+
+    fn show_pc(&self, out: &mut impl Write) -> io::Result<()> {
+        match Instruction::decode(self.machine.memory(), self.machine.pc()) {
+            Some(instruction) => writeln!(out, "{:04}: {}", instruction.address, instruction),
+            None => writeln!(
+                out,
+                "{:04}: .data {}",
+                self.machine.pc(),
+                self.machine.read(self.machine.pc())
+            ),
+        }
+    }
+
+    fn report(&mut self, stop: Stop, out: &mut impl Write) -> io::Result<()> {
+        for value in self.terminal.outputs.drain(..) {
+            writeln!(out, "output: {}", value)?;
+        }
+
+        for hit in self.watcher.log.drain(..) {
+            writeln!(out, "log: {}", hit)?;
+        }
+
+        match stop {
+            Stop::Stepped => {}
+            Stop::Breakpoint(address) => writeln!(out, "breakpoint at {:04}", address)?,
+            Stop::WriteBreakpoint(write) => writeln!(
+                out,
+                "write to {:04}: {} -> {}",
+                write.address, write.previous, write.value
+            )?,
+            Stop::ReadBreakpoint { address, value } => {
+                writeln!(out, "read of {:04}: {}", address, value)?
+            }
+            Stop::NeedsInput => writeln!(out, "waiting for input, queue some with `input`")?,
+            Stop::Halted => writeln!(out, "halted")?,
+            Stop::Error(error) => writeln!(out, "error: {}", error)?,
+            Stop::StartOfHistory => writeln!(out, "no more instructions to undo")?,
+        }
+
+        self.show_pc(out)
+    }
+
+    /// Runs one debugger command, returning `false` once the user asks to quit.
+    pub fn command(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        let watch = match words.as_slice() {
+            ["w", _, mode] | ["watch", _, mode] | ["log", _, mode] => match *mode {
+                "r" => Some(Watch::Read),
+                "w" => Some(Watch::Write),
+                "rw" => Some(Watch::ReadWrite),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if watch.is_some() {
+            words.pop();
+        }
+
+        let watch = watch.unwrap_or(Watch::Write);
+        let numbers: Result<Vec<i64>, _> = words.iter().skip(1).map(|w| w.parse()).collect();
+        let numbers = match numbers {
+            Ok(numbers) => numbers,
+            Err(_) => {
+                writeln!(out, "arguments must be integers")?;
+                return Ok(true);
+            }
+        };
+        let address = |i: usize| numbers.get(i).filter(|&&n| n >= 0).map(|&n| n as usize);
+
+        match (words.first().cloned().unwrap_or(""), address(0)) {
+            ("", _) => {}
+            ("s", _) | ("step", _) => {
+                let count = address(0).unwrap_or(1);
+                let mut stop = Stop::Stepped;
+
+                for _ in 0..count {
+                    stop = self.step();
+
+                    if stop != Stop::Stepped {
+                        break;
+                    }
+                }
+
+                self.report(stop, out)?;
+            }
+            ("c", _) | ("continue", _) => {
+                let stop = self.cont();
+                self.report(stop, out)?;
+            }
+            ("rs", _) | ("reverse-step", _) => {
+                let mut stop = Stop::Stepped;
+
+                for _ in 0..address(0).unwrap_or(1) {
+                    stop = self.reverse_step();
+
+                    if stop != Stop::Stepped {
+                        break;
+                    }
+                }
+
+                self.report(stop, out)?;
+            }
+            ("rc", _) | ("reverse-continue", _) => {
+                let stop = self.reverse_cont();
+                self.report(stop, out)?;
+            }
+            ("b", Some(address)) | ("break", Some(address)) => self.add_breakpoint(address),
+            ("w", Some(address)) | ("watch", Some(address)) => {
+                self.watcher.add(address, watch, Action::Pause)
+            }
+            ("log", Some(address)) => self.watcher.add(address, watch, Action::Log),
+            ("d", Some(address)) | ("delete", Some(address)) => {
+                if !self.remove_breakpoints(address) {
+                    writeln!(out, "no breakpoint at {:04}", address)?;
+                }
+            }
+            ("i", _) | ("info", _) => {
+                for address in &self.breakpoints {
+                    writeln!(out, "break {:04}", address)?;
+                }
+
+                for (address, watch, action) in self.watcher.watchpoints() {
+                    let command = match action {
+                        Action::Pause => "watch",
+                        Action::Log => "log",
+                    };
+
+                    writeln!(out, "{} {:04} {}", command, address, watch)?;
+                }
+            }
+            ("r", _) | ("regs", _) => writeln!(
+                out,
+                "pc {:04}  rb {}  steps {}",
+                self.machine.pc(),
+                self.machine.relative_base(),
+                self.machine.steps()
+            )?,
+            ("x", Some(address)) => {
+                let count = address_or(&numbers, 1, 1).min(MAX_CELLS);
+                let values: Vec<String> = (address..address.saturating_add(count))
+                    .map(|a| self.machine.read(a).to_string())
+                    .collect();
+
+                writeln!(out, "{:04}: {}", address, values.join(", "))?;
+            }
+            ("poke", Some(address)) if numbers.len() == 2 => {
+                if let Err(error) = self.machine.write(address, numbers[1]) {
+                    writeln!(out, "error: {}", error)?;
+                }
+            }
+            ("save", _) => self.save(),
+            ("restore", _) => {
+                if self.restore() {
+                    self.show_pc(out)?;
+                } else {
+                    writeln!(out, "nothing saved yet")?;
+                }
+            }
+            ("in", _) | ("input", _) => self.terminal.inputs.extend(numbers.iter().cloned()),
+            ("l", _) | ("list", _) => {
+                let mut pc = self.machine.pc();
+
+                for _ in 0..address_or(&numbers, 0, 5) {
+                    match Instruction::decode(self.machine.memory(), pc) {
+                        Some(instruction) => {
+                            writeln!(out, "{:04}: {}", pc, instruction)?;
+                            pc += instruction.size();
+                        }
+                        None => break,
+                    }
+                }
+            }
+            ("heat", _) => write!(
+                out,
+                "{}",
+                self.watcher.heatmap.render(address_or(&numbers, 0, 50))
+            )?,
+            ("h", _) | ("help", _) => writeln!(out, "{}", HELP)?,
+            ("q", _) | ("quit", _) => return Ok(false),
+            _ => writeln!(out, "unknown command `{}`, try `help`", line.trim())?,
+        }
+
+        Ok(true)
+    }
+
+    /// Reads commands until end of input or `quit`.
+    pub fn repl(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        self.show_pc(&mut out)?;
+        write!(out, "(icdb) ")?;
+        out.flush()?;
+
+        for line in input.lines() {
+            if !self.command(&line?, &mut out)? {
+                break;
+            }
+
+            write!(out, "(icdb) ")?;
+            out.flush()?;
+        }
+
+        writeln!(out)
+    }
+}
+
+fn address_or(numbers: &[i64], index: usize, default: usize) -> usize {
+    numbers
+        .get(index)
+        .filter(|&&n| n >= 0)
+        .map_or(default, |&n| n as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Debugger, Stop};
+    use crate::error::MachineError;
+    use crate::machine::MemoryWrite;
+
+    const JUMP_PROGRAM: [i64; 47] = [
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ];
+
+    #[test]
+    fn breakpoints() {
+        let mut debugger = Debugger::new(&JUMP_PROGRAM);
+
+        assert_eq!(debugger.cont(), Stop::NeedsInput);
+        debugger.terminal.inputs.push_back(9);
+
+        debugger.add_breakpoint(36);
+        assert_eq!(debugger.cont(), Stop::Breakpoint(36));
+
+        debugger.add_write_breakpoint(20);
+        assert_eq!(
+            debugger.cont(),
+            Stop::WriteBreakpoint(MemoryWrite {
+                address: 20,
+                previous: 1,
+                value: 1001,
+            })
+        );
+
+        assert_eq!(debugger.step(), Stop::Stepped);
+        assert_eq!(debugger.terminal.outputs.pop_front(), Some(1001));
+        assert_eq!(debugger.cont(), Stop::Halted);
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let mut debugger = Debugger::new(&JUMP_PROGRAM);
+        assert!(!debugger.restore());
+
+        debugger.terminal.inputs.push_back(8);
+        debugger.save();
+        assert_eq!(debugger.cont(), Stop::Halted);
+        assert_eq!(debugger.terminal.outputs.pop_front(), Some(1000));
+
+        assert!(debugger.restore());
+        assert_eq!(debugger.machine.pc(), 0);
+        assert_eq!(debugger.terminal.inputs, [8]);
+        debugger.terminal.inputs[0] = 7;
+        assert_eq!(debugger.cont(), Stop::Halted);
+        assert_eq!(debugger.terminal.outputs.pop_front(), Some(999));
+    }
+
+    #[test]
+    fn errors_stop_execution() {
+        let mut debugger = Debugger::new(&[1101, 1, 1, 3, 42]);
+
+        assert_eq!(
+            debugger.cont(),
+            Stop::Error(MachineError::UnknownOpCode { pc: 4, value: 42 })
+        );
+    }
+
+    #[test]
+    fn session() {
+        let mut debugger = Debugger::new(&JUMP_PROGRAM);
+        let script = "\
+list 2
+input 5
+break 31
+continue
+regs
+poke 32 -5
+x 31 2
+step 2
+c
+q
+step
+";
+        let mut out = Vec::new();
+        debugger.repl(script.as_bytes(), &mut out).unwrap();
+
+        let expected = "\
+0000: IN -> [21]
+(icdb) 0000: IN -> [21]
+0002: EQ [21], #8 -> [20]
+(icdb) (icdb) (icdb) breakpoint at 0031
+0031: OUT #999
+(icdb) pc 0031  rb 0  steps 5
+(icdb) (icdb) 0031: 104, -5
+(icdb) output: -5
+0046: HLT
+(icdb) halted
+0046: HLT
+(icdb) 
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn bounded_memory_commands() {
+        let mut debugger = Debugger::new(&[99]);
+        let script = "\
+x 0 100000000000
+poke 100000000000000 1
+";
+        let mut out = Vec::new();
+        debugger.repl(script.as_bytes(), &mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[1].matches(", ").count(), 255);
+        assert_eq!(
+            lines[2],
+            "(icdb) error: write to 100000000000000 past the memory limit of 16777216 cells at pc 0"
+        );
+        assert_eq!(debugger.machine.memory().len(), 1);
+    }
+
+    #[test]
+    fn watchpoints() {
+        let mut debugger = Debugger::new(&JUMP_PROGRAM);
+        let script = "\
+input 8
+watch 21 r
+log 20 rw
+info
+c
+heat 25
+delete 21
+c
+";
+        let mut out = Vec::new();
+        debugger.repl(script.as_bytes(), &mut out).unwrap();
+
+        let expected = "\
+0000: IN -> [21]
+(icdb) (icdb) (icdb) (icdb) log 0020 rw
+watch 0021 r
+(icdb) log: step 1: 0002 writes [0020] = 1
+read of 0021: 8
+0006: JT [20], #22
+(icdb)       reads                      writes
+0000                       @                         @@
+(icdb) (icdb) output: 1000
+log: step 2: 0006 reads [0020] = 1
+log: step 3: 0022 writes [0020] = 1000
+log: step 4: 0026 reads [0020] = 1000
+halted
+0046: HLT
+(icdb) 
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut debugger = Debugger::new(&JUMP_PROGRAM);
+        let script = "\
+input 9
+c
+watch 20
+rc
+rc
+rs 5
+rs
+rs
+regs
+c
+";
+        let mut out = Vec::new();
+        debugger.repl(script.as_bytes(), &mut out).unwrap();
+
+        let expected = "\
+0000: IN -> [21]
+(icdb) (icdb) output: 1001
+halted
+0046: HLT
+(icdb) (icdb) write to 0020: 1 -> 1001
+0036: ADD #1000, #1 -> [20]
+(icdb) write to 0020: 0 -> 1
+0009: LT #8, [21] -> [20]
+(icdb) write to 0020: 0 -> 0
+0002: EQ [21], #8 -> [20]
+(icdb) 0000: IN -> [21]
+(icdb) no more instructions to undo
+0000: IN -> [21]
+(icdb) pc 0000  rb 0  steps 0
+(icdb) write to 0020: 0 -> 0
+0006: JT [20], #22
+(icdb) 
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
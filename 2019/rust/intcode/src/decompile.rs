@@ -0,0 +1,880 @@
+//! Lifts an Intcode program into structured pseudo-code.
+//!
+//! The control-flow graph from [`analysis`](crate::analysis) is turned back into `if`, `else`,
+//! `while`, `do ... while` and `loop` wherever a region of it has a single entry, with `goto` left
+//! for everything else. Memory cells become variables named after their address, so `[21]` reads
+//! as `v21`; an operand some instruction overwrites becomes a variable too, so a jump through a
+//! patched operand reads as `goto mem[v8]`.
+
+use crate::analysis::{analyze, Analysis};
+use crate::instruction::Instruction;
+use crate::opcode::{OpCode, ParameterMode};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Write};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Value {
+    Const(i64),
+    Place(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Const(value) => write!(f, "{}", value),
+            Value::Place(place) => write!(f, "{}", place),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Expr {
+    Binary(Value, &'static str, Value),
+    Input,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Cond {
+    left: Value,
+    op: &'static str,
+    right: Value,
+}
+
+impl Cond {
+    fn negate(self) -> Cond {
+        let op = match self.op {
+            "<" => ">=",
+            ">=" => "<",
+            "==" => "!=",
+            _ => "==",
+        };
+
+        Cond { op, ..self }
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.left, self.op, self.right)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Stmt {
+    Label(usize),
+    Assign {
+        target: String,
+        expr: Expr,
+    },
+    Output(Value),
+    AdjustBase(Value),
+    Halt,
+    Goto(usize),
+    /// A jump to an address read from memory.
+    GotoValue(Value),
+    Break,
+    Continue,
+    /// Execution runs on into cells that do not hold an instruction before the program runs.
+    IntoData(usize),
+    /// An instruction patched at run time into one of these.
+    Rewritten(Vec<Instruction>),
+    If {
+        cond: Cond,
+        then: Vec<Stmt>,
+        otherwise: Vec<Stmt>,
+    },
+    Loop(Vec<Stmt>),
+    While {
+        cond: Cond,
+        body: Vec<Stmt>,
+    },
+    DoWhile {
+        body: Vec<Stmt>,
+        cond: Cond,
+    },
+}
+
+impl Stmt {
+    /// Whether execution never continues with the statement after this one.
+    fn transfers(&self) -> bool {
+        matches!(
+            self,
+            Stmt::Halt
+                | Stmt::Goto(_)
+                | Stmt::GotoValue(_)
+                | Stmt::Break
+                | Stmt::Continue
+                | Stmt::IntoData(_)
+        )
+    }
+}
+
+/// Where `break` and `continue` lead inside the innermost loop.
+#[derive(Clone, Copy, Default)]
+struct Context {
+    header: Option<usize>,
+    exit: Option<usize>,
+}
+
+struct Decompiler<'a> {
+    program: &'a [i64],
+    analysis: Analysis,
+    /// Operand cells some instruction writes to.
+    patched: BTreeSet<usize>,
+    predecessors: HashMap<usize, Vec<usize>>,
+    variables: BTreeSet<usize>,
+}
+
+fn variable(address: usize) -> String {
+    format!("v{}", address)
+}
+
+impl<'a> Decompiler<'a> {
+    fn new(program: &'a [i64]) -> Decompiler<'a> {
+        let analysis = analyze(program);
+        let patched = analysis.patches.iter().map(|patch| patch.target).collect();
+        let mut predecessors: HashMap<usize, Vec<usize>> = HashMap::new();
+
+        for (&from, block) in &analysis.blocks {
+            for &(to, _) in &block.successors {
+                predecessors.entry(to).or_default().push(from);
+            }
+        }
+
+        Decompiler {
+            program,
+            analysis,
+            patched,
+            predecessors,
+            variables: BTreeSet::new(),
+        }
+    }
+
+    fn place(&mut self, address: i64) -> String {
+        if address < 0 {
+            return format!("mem[{}]", address);
+        }
+
+        self.variables.insert(address as usize);
+        variable(address as usize)
+    }
+
+    /// The `index`th parameter of `instruction`, read through a variable if it gets patched.
+    fn value(&mut self, instruction: &Instruction, index: usize) -> Value {
+        let parameter = instruction.parameters[index];
+        let cell = instruction.address + index + 1;
+
+        if self.patched.contains(&cell) {
+            let operand = self.place(cell as i64);
+
+            return Value::Place(match parameter.mode {
+                ParameterMode::Immediate => operand,
+                ParameterMode::Position => format!("mem[{}]", operand),
+                ParameterMode::Relative => format!("rb[{}]", operand),
+            });
+        }
+
+        match parameter.mode {
+            ParameterMode::Immediate => Value::Const(parameter.value),
+            ParameterMode::Position => Value::Place(self.place(parameter.value)),
+            ParameterMode::Relative => Value::Place(format!("rb[{}]", parameter.value)),
+        }
+    }
+
+    fn statement(&mut self, instruction: &Instruction) -> Stmt {
+        let op = match instruction.opcode {
+            OpCode::Add => "+",
+            OpCode::Multiply => "*",
+            OpCode::LessThan => "<",
+            OpCode::Equals => "==",
+            OpCode::Input => {
+                let target = self.value(instruction, 0).to_string();
+                return Stmt::Assign {
+                    target,
+                    expr: Expr::Input,
+                };
+            }
+            OpCode::Output => return Stmt::Output(self.value(instruction, 0)),
+            OpCode::AdjustRelativeBase => return Stmt::AdjustBase(self.value(instruction, 0)),
+            OpCode::End => return Stmt::Halt,
+            OpCode::JumpIfTrue | OpCode::JumpIfNot => unreachable!("jumps end blocks"),
+        };
+
+        let left = self.value(instruction, 0);
+        let right = self.value(instruction, 1);
+
+        Stmt::Assign {
+            target: self.value(instruction, 2).to_string(),
+            expr: Expr::Binary(left, op, right),
+        }
+    }
+
+    /// The condition under which `jump` is taken. A comparison stored just before into the value
+    /// tested is inlined.
+    fn condition(&mut self, jump: &Instruction, before: &[Stmt]) -> Cond {
+        let tested = self.value(jump, 0);
+        let cond = match before.last() {
+            Some(Stmt::Assign {
+                target,
+                expr: Expr::Binary(left, op @ "<", right),
+            })
+            | Some(Stmt::Assign {
+                target,
+                expr: Expr::Binary(left, op @ "==", right),
+            }) if Value::Place(target.clone()) == tested && *left != tested && *right != tested => {
+                Cond {
+                    left: left.clone(),
+                    op,
+                    right: right.clone(),
+                }
+            }
+            _ => Cond {
+                left: tested,
+                op: "!=",
+                right: Value::Const(0),
+            },
+        };
+
+        match jump.opcode {
+            OpCode::JumpIfNot => cond.negate(),
+            _ => cond,
+        }
+    }
+
+    /// Whether `start..end` can only be entered through the block at `start`.
+    fn single_entry(&self, start: usize, end: usize) -> bool {
+        self.analysis
+            .blocks
+            .range(start + 1..end)
+            .all(|(address, _)| {
+                !self.analysis.entries.contains(address)
+                    && self
+                        .predecessors
+                        .get(address)
+                        .is_none_or(|from| from.iter().all(|&from| from >= start && from < end))
+            })
+    }
+
+    /// The address just past the loop headed at `header`, if it can be written as a loop
+    /// ending by `end`.
+    fn loop_exit(&self, header: usize, end: usize) -> Option<usize> {
+        let l = self.analysis.loops.iter().find(|l| l.header == header)?;
+        let exit = l
+            .blocks
+            .iter()
+            .map(|block| self.analysis.blocks[block].end())
+            .max()?;
+
+        if l.blocks.iter().all(|&block| block >= header) && exit <= end {
+            Some(exit).filter(|&exit| self.single_entry(header, exit))
+        } else {
+            None
+        }
+    }
+
+    fn transfer(target: usize, context: Context) -> Stmt {
+        if Some(target) == context.header {
+            Stmt::Continue
+        } else if Some(target) == context.exit {
+            Stmt::Break
+        } else {
+            Stmt::Goto(target)
+        }
+    }
+
+    /// Adds the statements for `jump`, which ends a block at `next`, and returns where the
+    /// sequence goes on.
+    fn jump(
+        &mut self,
+        jump: &Instruction,
+        next: usize,
+        end: usize,
+        context: Context,
+        stmts: &mut Vec<Stmt>,
+    ) -> usize {
+        let taken = match jump.jump_target() {
+            Some(target) => Decompiler::transfer(target, context),
+            None => Stmt::GotoValue(self.value(jump, 1)),
+        };
+
+        if !jump.falls_through() {
+            // A jump to the block that comes next anyway says nothing.
+            let following = self.analysis.blocks.range(next..end).next();
+
+            if !matches!((&taken, following), (Stmt::Goto(target), Some((start, _))) if target == start)
+            {
+                stmts.push(taken);
+            }
+
+            return next;
+        }
+
+        let cond = self.condition(jump, stmts);
+
+        if let Stmt::Goto(target) = taken {
+            if target > next && target <= end && self.single_entry(next, target) {
+                let mut then = self.sequence(next, target, context, false);
+                let mut otherwise = Vec::new();
+                let mut resume = target;
+
+                if let Some(&Stmt::Goto(join)) = then.last() {
+                    if join > target && join <= end && self.single_entry(target, join) {
+                        then.pop();
+                        otherwise = self.sequence(target, join, context, false);
+                        resume = join;
+                    }
+                }
+
+                stmts.push(Stmt::If {
+                    cond: cond.negate(),
+                    then,
+                    otherwise,
+                });
+                return resume;
+            }
+        }
+
+        stmts.push(Stmt::If {
+            cond,
+            then: vec![taken],
+            otherwise: Vec::new(),
+        });
+        next
+    }
+
+    /// The statements for the blocks in `start..end`. With `in_loop`, the first block is the
+    /// header of the loop being built rather than the start of a new one.
+    fn sequence(&mut self, start: usize, end: usize, context: Context, in_loop: bool) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+        let mut address = start;
+        let mut header = in_loop;
+
+        while address < end {
+            let block = match self.analysis.blocks.range(address..end).next() {
+                Some((_, block)) => block.clone(),
+                None => break,
+            };
+            let first = block.start();
+
+            if !header {
+                if let Some(exit) = self.loop_exit(first, end) {
+                    let inner = Context {
+                        header: Some(first),
+                        exit: Some(exit),
+                    };
+                    let body = self.sequence(first, exit, inner, true);
+
+                    structure_loop(body, &mut stmts);
+                    address = exit;
+                    continue;
+                }
+            }
+
+            header = false;
+            stmts.push(Stmt::Label(first));
+
+            let (last, body) = block
+                .instructions
+                .split_last()
+                .expect("blocks are not empty");
+
+            for instruction in body {
+                let stmt = self.statement(instruction);
+                stmts.push(stmt);
+            }
+
+            address = block.end();
+
+            match last.opcode {
+                OpCode::JumpIfTrue | OpCode::JumpIfNot => {
+                    address = self.jump(last, address, end, context, &mut stmts);
+                }
+                _ if self.analysis.rewritten.contains_key(&last.address) => {
+                    let forms = self.analysis.rewritten[&last.address].clone();
+                    stmts.push(Stmt::Rewritten(forms));
+                }
+                _ => {
+                    let stmt = self.statement(last);
+                    stmts.push(stmt);
+
+                    if last.falls_through() && !self.analysis.blocks.contains_key(&address) {
+                        stmts.push(Stmt::IntoData(address));
+                    }
+                }
+            }
+        }
+
+        stmts
+    }
+}
+
+/// Whether `stmts` continue the loop they are in anywhere but inside a nested loop.
+fn continues(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        Stmt::Continue => true,
+        Stmt::If {
+            then, otherwise, ..
+        } => continues(then) || continues(otherwise),
+        _ => false,
+    })
+}
+
+/// Turns the body of a `loop` into the tidiest loop statement that means the same.
+fn structure_loop(mut body: Vec<Stmt>, stmts: &mut Vec<Stmt>) {
+    // Falling off the end of the body leaves the loop.
+    if !body.last().is_some_and(Stmt::transfers) {
+        body.push(Stmt::Break);
+    }
+
+    if body.last() == Some(&Stmt::Continue) {
+        body.pop();
+    }
+
+    // The header's label goes before the loop, where jumping to it means the same.
+    if let Some(&Stmt::Label(header)) = body.first() {
+        body.remove(0);
+        stmts.push(Stmt::Label(header));
+    }
+
+    if body.len() >= 2 && body[body.len() - 1] == Stmt::Break {
+        if let Stmt::If {
+            cond,
+            then,
+            otherwise,
+        } = &body[body.len() - 2]
+        {
+            if then[..] == [Stmt::Continue]
+                && otherwise.is_empty()
+                && !continues(&body[..body.len() - 2])
+            {
+                let cond = cond.clone();
+                body.truncate(body.len() - 2);
+                stmts.push(Stmt::DoWhile { body, cond });
+                return;
+            }
+        }
+    }
+
+    if let Some(Stmt::If {
+        cond,
+        then,
+        otherwise,
+    }) = body.first()
+    {
+        if then[..] == [Stmt::Break] && otherwise.is_empty() {
+            let cond = cond.clone().negate();
+            body.remove(0);
+            stmts.push(Stmt::While { cond, body });
+            return;
+        }
+    }
+
+    stmts.push(Stmt::Loop(body));
+}
+
+fn targets(stmts: &[Stmt], labels: &mut BTreeSet<usize>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Goto(target) => {
+                labels.insert(*target);
+            }
+            Stmt::If {
+                then, otherwise, ..
+            } => {
+                targets(then, labels);
+                targets(otherwise, labels);
+            }
+            Stmt::Loop(body) | Stmt::While { body, .. } | Stmt::DoWhile { body, .. } => {
+                targets(body, labels)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn assignment(target: &str, expr: &Expr) -> String {
+    let place = Value::Place(target.to_string());
+
+    match expr {
+        Expr::Input => format!("{} = input()", target),
+        Expr::Binary(Value::Const(a), op, Value::Const(b)) => {
+            let folded = match *op {
+                "+" => a.checked_add(*b),
+                "*" => a.checked_mul(*b),
+                "<" => Some((a < b) as i64),
+                _ => Some((a == b) as i64),
+            };
+
+            match folded {
+                Some(value) => format!("{} = {}", target, value),
+                None => format!("{} = {} {} {}", target, a, op, b),
+            }
+        }
+        Expr::Binary(left, op @ "+", right) | Expr::Binary(left, op @ "*", right) => {
+            // Commutative, so keep any constant on the right.
+            let (left, right) = match left {
+                Value::Const(_) => (right, left),
+                _ => (left, right),
+            };
+
+            match (*op, right) {
+                ("+", Value::Const(0)) | ("*", Value::Const(1)) => {
+                    format!("{} = {}", target, left)
+                }
+                ("*", Value::Const(0)) => format!("{} = 0", target),
+                ("*", Value::Const(-1)) => format!("{} = -{}", target, left),
+                ("+", Value::Const(k)) if *k < 0 && *left == place => {
+                    format!("{} -= {}", target, -(*k as i128))
+                }
+                ("+", Value::Const(k)) if *k < 0 => {
+                    format!("{} = {} - {}", target, left, -(*k as i128))
+                }
+                (op, right) if *left == place => format!("{} {}= {}", target, op, right),
+                (op, right) if *right == place => format!("{} {}= {}", target, op, left),
+                (op, right) => format!("{} = {} {} {}", target, left, op, right),
+            }
+        }
+        Expr::Binary(left, op, right) => format!("{} = {} {} {}", target, left, op, right),
+    }
+}
+
+/// `--x` or `++x` when `stmt` steps `x` by one.
+fn step(stmt: &Stmt) -> Option<(String, &'static str)> {
+    match stmt {
+        Stmt::Assign {
+            target,
+            expr: Expr::Binary(left, "+", right),
+        } => {
+            let place = Value::Place(target.clone());
+
+            match (left, right) {
+                (Value::Const(k), other) | (other, Value::Const(k)) if *other == place => match k {
+                    -1 => Some((target.clone(), "--")),
+                    1 => Some((target.clone(), "++")),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+struct Printer<'a> {
+    labels: &'a BTreeSet<usize>,
+    out: String,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, depth: usize, text: &str) {
+        writeln!(self.out, "{:width$}{}", "", text, width = depth * 4).unwrap();
+    }
+
+    fn simple(stmt: &Stmt) -> Option<String> {
+        match stmt {
+            Stmt::Assign { target, expr } => Some(format!("{};", assignment(target, expr))),
+            Stmt::Output(value) => Some(format!("output({});", value)),
+            Stmt::AdjustBase(value) => Some(format!("rb += {};", value)),
+            Stmt::Halt => Some("halt;".to_string()),
+            Stmt::Goto(target) => Some(format!("goto L{:04};", target)),
+            Stmt::GotoValue(value) => Some(format!("goto {};", value)),
+            Stmt::Break => Some("break;".to_string()),
+            Stmt::Continue => Some("continue;".to_string()),
+            Stmt::IntoData(address) => Some(format!("// runs on into data at {:04}", address)),
+            _ => None,
+        }
+    }
+
+    fn block(&mut self, depth: usize, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.stmt(depth, stmt);
+        }
+    }
+
+    fn stmt(&mut self, depth: usize, stmt: &Stmt) {
+        if let Some(text) = Printer::simple(stmt) {
+            return self.line(depth, &text);
+        }
+
+        match stmt {
+            Stmt::Label(address) => {
+                if self.labels.contains(address) {
+                    self.line(depth, &format!("L{:04}:", address));
+                }
+            }
+            Stmt::Rewritten(forms) => {
+                let address = forms[0].address;
+                self.line(depth, &format!("// {:04} is patched into one of:", address));
+
+                for form in forms {
+                    self.line(depth, &format!("//     {}", form));
+                }
+            }
+            Stmt::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let jump = match &then[..] {
+                    [only] if only.transfers() && otherwise.is_empty() => Printer::simple(only),
+                    _ => None,
+                };
+
+                if let Some(jump) = jump {
+                    return self.line(depth, &format!("if ({}) {}", cond, jump));
+                }
+
+                self.line(depth, &format!("if ({}) {{", cond));
+                self.block(depth + 1, then);
+
+                if !otherwise.is_empty() {
+                    self.line(depth, "} else {");
+                    self.block(depth + 1, otherwise);
+                }
+
+                self.line(depth, "}");
+            }
+            Stmt::Loop(body) => {
+                self.line(depth, "loop {");
+                self.block(depth + 1, body);
+                self.line(depth, "}");
+            }
+            Stmt::While { cond, body } => {
+                self.line(depth, &format!("while ({}) {{", cond));
+                self.block(depth + 1, body);
+                self.line(depth, "}");
+            }
+            Stmt::DoWhile { body, cond } => {
+                // A counter stepped just before it is tested reads as the classic countdown.
+                let counted = body.last().and_then(step).filter(|(counter, _)| {
+                    cond.left == Value::Place(counter.clone()) && cond.right == Value::Const(0)
+                });
+
+                self.line(depth, "do {");
+
+                match counted {
+                    Some((counter, step)) => {
+                        self.block(depth + 1, &body[..body.len() - 1]);
+                        let test = format!("{}{} {} 0", step, counter, cond.op);
+                        self.line(depth, &format!("}} while ({});", test));
+                    }
+                    None => {
+                        self.block(depth + 1, body);
+                        self.line(depth, &format!("}} while ({});", cond));
+                    }
+                }
+            }
+            _ => unreachable!("simple statements are printed above"),
+        }
+    }
+}
+
+/// Decompiles `program` into pseudo-code, starting with the initial value of every variable.
+pub fn decompile(program: &[i64]) -> String {
+    let mut decompiler = Decompiler::new(program);
+    let mut stmts = decompiler.sequence(0, usize::MAX, Context::default(), false);
+
+    if !decompiler.analysis.blocks.contains_key(&0) {
+        stmts.insert(0, Stmt::IntoData(0));
+    }
+
+    let mut labels = BTreeSet::new();
+    targets(&stmts, &mut labels);
+    labels.extend(
+        decompiler
+            .analysis
+            .entries
+            .iter()
+            .filter(|&&entry| entry != 0),
+    );
+    labels.extend(
+        decompiler
+            .analysis
+            .rewritten
+            .values()
+            .flatten()
+            .filter_map(Instruction::jump_target),
+    );
+
+    let mut printer = Printer {
+        labels: &labels,
+        out: String::new(),
+    };
+
+    for &address in &decompiler.variables {
+        let initial = decompiler.program.get(address).cloned().unwrap_or(0);
+        let owner = decompiler
+            .analysis
+            .blocks
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .find(|i| i.address <= address && address < i.address + i.size());
+
+        let declaration = match owner {
+            Some(i) => format!(
+                "var {} = {}; // in {:04}",
+                variable(address),
+                initial,
+                i.address
+            ),
+            None => format!("var {} = {};", variable(address), initial),
+        };
+        printer.line(0, &declaration);
+    }
+
+    if !decompiler.variables.is_empty() {
+        printer.out.push('\n');
+    }
+
+    printer.block(0, &stmts);
+    printer.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decompile;
+    use crate::examples::EXAMPLES;
+    use crate::loader;
+
+    #[test]
+    fn countdown() {
+        let program = [3, 12, 4, 12, 1001, 12, -1, 12, 1005, 12, 2, 99, 0];
+        let expected = "\
+var v12 = 0;
+
+v12 = input();
+do {
+    output(v12);
+} while (--v12 != 0);
+halt;
+";
+
+        assert_eq!(decompile(&program), expected);
+    }
+
+    #[test]
+    fn if_else() {
+        let expected = "\
+var v20 = 0;
+var v21 = 0;
+
+v21 = input();
+v20 = v21 == 8;
+if (v21 != 8) {
+    v20 = 8 < v21;
+    if (8 >= v21) goto L0031;
+    goto L0036;
+}
+v20 = v21 * 125;
+output(v20);
+goto L0046;
+L0031:
+output(999);
+goto L0046;
+L0036:
+v20 = 1001;
+output(v20);
+L0046:
+halt;
+";
+
+        assert_eq!(decompile(EXAMPLES[9]), expected);
+    }
+
+    #[test]
+    fn while_loop() {
+        // Sums the inputs until a zero.
+        let program = [
+            3, 19, 1006, 19, 14, 1, 19, 20, 20, 3, 19, 1105, 1, 2, 4, 20, 99, 0, 0, 0, 0,
+        ];
+        let expected = "\
+var v19 = 0;
+var v20 = 0;
+
+v19 = input();
+while (v19 != 0) {
+    v20 += v19;
+    v19 = input();
+}
+output(v20);
+halt;
+";
+
+        assert_eq!(decompile(&program), expected);
+    }
+
+    #[test]
+    fn patched_code() {
+        // Turns the cell after the input into an output instruction.
+        let program = [3, 9, 1, 9, 6, 6, 0, 7, 99, 0];
+        let expected = "\
+var v6 = 0; // in 0006
+var v9 = 0;
+
+v9 = input();
+v6 += v9;
+// 0006 is patched into one of:
+//     IN -> [7]
+//     OUT [7]
+//     ARB [7]
+//     HLT
+halt;
+";
+
+        assert_eq!(decompile(&program), expected);
+    }
+
+    #[test]
+    fn data_only() {
+        assert_eq!(decompile(&[0]), "// runs on into data at 0000\n");
+        assert_eq!(decompile(&[]), "// runs on into data at 0000\n");
+    }
+
+    #[test]
+    fn jump_table() {
+        let program = [
+            3, 8, 1001, 8, 10, 8, 105, 1, 0, 0, 12, 16, 104, 1, 99, 0, 104, 2, 99,
+        ];
+        let expected = "\
+var v8 = 0; // in 0006
+
+v8 = input();
+v8 += 10;
+goto mem[v8];
+L0012:
+output(1);
+halt;
+L0016:
+output(2);
+halt;
+";
+
+        assert_eq!(decompile(&program), expected);
+    }
+
+    #[test]
+    fn overlapping_guess() {
+        // Cell 11 points into the middle of the ADD at 3, which once made analysis spin.
+        let program = [1105, 1, 5, 1101, 0, 104, 50, 5, 10, 11, 99, 3];
+        let expected = "\
+var v10 = 99; // in 0010
+var v11 = 3;
+
+output(50);
+if (v10 != 0) goto v11;
+halt;
+";
+
+        assert_eq!(decompile(&program), expected);
+    }
+
+    #[test]
+    fn diagnostic_program() {
+        // The 1100 at 6 becomes an add for system 1 and a jump to the tests at 238 for system 5.
+        let program = loader::parse(include_str!("../../05a/input")).expect("Bad program");
+        assert_eq!(
+            decompile(&program),
+            include_str!("../../05a/decompiled.txt")
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Turns an Intcode image back into a readable listing.
+//!
+//! Code is found by walking every path from pc 0, following fall-through and constant jump
+//! targets. Cells no path reaches are listed as `.data`; jumps whose target is read from memory
+//! cannot be followed, so code only reached that way also shows up as data.
+
+use crate::instruction::{Instruction, Parameter};
+use crate::opcode::ParameterMode;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// Maximum number of values printed on one `.data` line.
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Line {
+    Label(usize),
+    Instruction(Instruction),
+    Data { address: usize, values: Vec<i64> },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Listing {
+    pub lines: Vec<Line>,
+}
+
+impl Listing {
+    /// Addresses that are the constant target of some jump.
+    pub fn labels(&self) -> BTreeSet<usize> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Label(address) => Some(*address),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Instruction(instruction) => Some(instruction),
+            _ => None,
+        })
+    }
+}
+
+pub fn label_name(address: usize) -> String {
+    format!("L{:04}", address)
+}
+
+fn write_parameter(
+    f: &mut fmt::Formatter,
+    parameter: &Parameter,
+    labels: &BTreeSet<usize>,
+) -> fmt::Result {
+    match parameter.mode {
+        ParameterMode::Immediate
+            if parameter.value >= 0 && labels.contains(&(parameter.value as usize)) =>
+        {
+            write!(f, "#{}", label_name(parameter.value as usize))
+        }
+        _ => write!(f, "{}", parameter),
+    }
+}
+
+fn write_instruction(
+    f: &mut fmt::Formatter,
+    instruction: &Instruction,
+    labels: &BTreeSet<usize>,
+) -> fmt::Result {
+    write!(f, "{:04}: ", instruction.address)?;
+
+    if instruction.jump_target().is_none() {
+        return writeln!(f, "{}", instruction);
+    }
+
+    // Jumps name their constant target instead of printing the raw address.
+    write!(f, "{} ", instruction.opcode.mnemonic().to_uppercase())?;
+    write_parameter(f, &instruction.parameters[0], labels)?;
+    write!(f, ", ")?;
+    write_parameter(f, &instruction.parameters[1], labels)?;
+    writeln!(f)
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let labels = self.labels();
+
+        for line in &self.lines {
+            match line {
+                Line::Label(address) => writeln!(f, "{}:", label_name(*address))?,
+                Line::Instruction(instruction) => write_instruction(f, instruction, &labels)?,
+                Line::Data { address, values } => {
+                    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                    writeln!(f, "{:04}: .data {}", address, values.join(", "))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds every instruction reachable from pc 0, keyed by address.
+pub fn reachable(program: &[i64]) -> HashMap<usize, Instruction> {
+    let mut instructions = HashMap::new();
+    explore(program, &mut instructions, &[0]);
+    instructions
+}
+
+/// Adds every instruction reachable from `entries` to `instructions`, leaving the cells of those
+/// already there alone.
+pub fn explore(program: &[i64], instructions: &mut HashMap<usize, Instruction>, entries: &[usize]) {
+    let mut owner: Vec<Option<usize>> = vec![None; program.len()];
+    let mut pending: Vec<usize> = entries.iter().rev().cloned().collect();
+
+    for instruction in instructions.values() {
+        let end = (instruction.address + instruction.size()).min(program.len());
+
+        for cell in owner[instruction.address..end].iter_mut() {
+            *cell = Some(instruction.address);
+        }
+    }
+
+    while let Some(address) = pending.pop() {
+        if address >= program.len() || owner[address].is_some() {
+            continue;
+        }
+
+        let instruction = match Instruction::decode(program, address) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+
+        let end = (address + instruction.size()).min(program.len());
+
+        // Instructions overlapping one already decoded are left for the data listing.
+        if owner[address..end].iter().any(|cell| cell.is_some()) {
+            continue;
+        }
+
+        for cell in owner[address..end].iter_mut() {
+            *cell = Some(address);
+        }
+
+        if instruction.falls_through() {
+            pending.push(address + instruction.size());
+        }
+
+        if let Some(target) = instruction.jump_target() {
+            pending.push(target);
+        }
+
+        instructions.insert(address, instruction);
+    }
+}
+
+/// Disassembles `program` into a listing with labelled jump targets and `.data` regions.
+pub fn disassemble(program: &[i64]) -> Listing {
+    let instructions = reachable(program);
+    let labels: BTreeSet<usize> = instructions
+        .values()
+        .filter_map(|instruction| instruction.jump_target())
+        .filter(|&target| instructions.contains_key(&target))
+        .collect();
+
+    let mut listing = Listing::default();
+    let mut address = 0;
+
+    while address < program.len() {
+        if let Some(instruction) = instructions.get(&address) {
+            if labels.contains(&address) {
+                listing.lines.push(Line::Label(address));
+            }
+
+            listing.lines.push(Line::Instruction(instruction.clone()));
+            address += instruction.size();
+            continue;
+        }
+
+        let start = address;
+
+        while address < program.len()
+            && address - start < DATA_PER_LINE
+            && !instructions.contains_key(&address)
+        {
+            address += 1;
+        }
+
+        listing.lines.push(Line::Data {
+            address: start,
+            values: program[start..address].to_vec(),
+        });
+    }
+
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disassemble;
+
+    #[test]
+    fn jump_program() {
+        let program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+
+        let expected = "\
+0000: IN -> [21]
+0002: EQ [21], #8 -> [20]
+0006: JT [20], #L0022
+0009: LT #8, [21] -> [20]
+0013: JF [20], #L0031
+0016: JF #0, #L0036
+0019: .data 98, 0, 0
+L0022:
+0022: MUL [21], #125 -> [20]
+0026: OUT [20]
+0028: JT #1, #L0046
+L0031:
+0031: OUT #999
+0033: JT #1, #L0046
+L0036:
+0036: ADD #1000, #1 -> [20]
+0040: OUT [20]
+0042: JT #1, #L0046
+0045: .data 98
+L0046:
+0046: HLT
+";
+
+        assert_eq!(disassemble(&program).to_string(), expected);
+    }
+
+    #[test]
+    fn unreachable_data() {
+        let program = [1105, 1, 5, 42, 43, 99];
+        let expected = "\
+0000: JT #1, #L0005
+0003: .data 42, 43
+L0005:
+0005: HLT
+";
+
+        assert_eq!(disassemble(&program).to_string(), expected);
+    }
+}
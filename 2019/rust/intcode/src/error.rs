@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt;
+
+/// Everything that can stop a [`Machine`](crate::Machine) short of an `End` instruction.
+///
+/// Each variant carries the instruction pointer of the instruction that failed; the machine is
+/// left parked on that instruction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MachineError {
+    /// The two low digits of the instruction are not a known opcode.
+    UnknownOpCode { pc: usize, value: i64 },
+    /// A parameter mode digit is not 0, 1 or 2.
+    InvalidParameterMode {
+        pc: usize,
+        position: usize,
+        mode: i64,
+    },
+    /// A parameter resolved to an address below zero.
+    NegativeAddress { pc: usize, address: i64 },
+    /// The parameter an instruction writes through is in immediate mode.
+    ImmediateWrite { pc: usize, position: usize },
+    /// An input instruction ran but the terminal had nothing left to give.
+    InputExhausted { pc: usize },
+    /// The machine executed its configured maximum number of instructions.
+    StepLimitExceeded { pc: usize, steps: u64 },
+    /// A value used as an instruction, address or jump target does not fit in 64 bits, or
+    /// arithmetic overflows the machine's word.
+    ValueOutOfRange { pc: usize },
+    /// The machine ran past its configured wall-clock timeout.
+    TimedOut { pc: usize, steps: u64 },
+    /// The machine came back to a state it was in before without reading input in between, so
+    /// it will repeat the instructions at `first..=last` forever, one round every `period` steps.
+    InfiniteLoop {
+        pc: usize,
+        first: usize,
+        last: usize,
+        period: u64,
+    },
+    /// A write to `address` would grow memory past [`MEMORY_LIMIT`](crate::MEMORY_LIMIT) cells.
+    MemoryLimitExceeded { pc: usize, address: usize },
+}
+
+impl MachineError {
+    pub fn pc(&self) -> usize {
+        match *self {
+            MachineError::UnknownOpCode { pc, .. }
+            | MachineError::InvalidParameterMode { pc, .. }
+            | MachineError::NegativeAddress { pc, .. }
+            | MachineError::ImmediateWrite { pc, .. }
+            | MachineError::InputExhausted { pc }
+            | MachineError::StepLimitExceeded { pc, .. }
+            | MachineError::ValueOutOfRange { pc }
+            | MachineError::TimedOut { pc, .. }
+            | MachineError::InfiniteLoop { pc, .. }
+            | MachineError::MemoryLimitExceeded { pc, .. } => pc,
+        }
+    }
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MachineError::UnknownOpCode { pc, value } => {
+                write!(f, "unknown opcode {} at pc {}", value, pc)
+            }
+            MachineError::InvalidParameterMode { pc, position, mode } => write!(
+                f,
+                "invalid mode {} for parameter {} at pc {}",
+                mode, position, pc
+            ),
+            MachineError::NegativeAddress { pc, address } => {
+                write!(f, "negative address {} at pc {}", address, pc)
+            }
+            MachineError::ImmediateWrite { pc, position } => write!(
+                f,
+                "write through parameter {} in immediate mode at pc {}",
+                position, pc
+            ),
+            MachineError::InputExhausted { pc } => write!(f, "input exhausted at pc {}", pc),
+            MachineError::StepLimitExceeded { pc, steps } => {
+                write!(f, "step limit of {} exceeded at pc {}", steps, pc)
+            }
+            MachineError::ValueOutOfRange { pc } => write!(f, "value out of range at pc {}", pc),
+            MachineError::TimedOut { pc, steps } => {
+                write!(f, "timed out after {} steps at pc {}", steps, pc)
+            }
+            MachineError::InfiniteLoop {
+                pc,
+                first,
+                last,
+                period,
+            } => write!(
+                f,
+                "infinite loop over pc {}..={} every {} steps at pc {}",
+                first, last, period, pc
+            ),
+            MachineError::MemoryLimitExceeded { pc, address } => write!(
+                f,
+                "write to {} past the memory limit of {} cells at pc {}",
+                address,
+                crate::MEMORY_LIMIT,
+                pc
+            ),
+        }
+    }
+}
+
+impl Error for MachineError {}
@@ -0,0 +1,189 @@
+use crate::opcode::{OpCode, ParameterMode};
+use num_traits::FromPrimitive;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Parameter {
+    pub mode: ParameterMode,
+    pub value: i64,
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            ParameterMode::Position => write!(f, "[{}]", self.value),
+            ParameterMode::Immediate => write!(f, "#{}", self.value),
+            ParameterMode::Relative => write!(f, "[r{}]", self.value),
+        }
+    }
+}
+
+/// A single instruction decoded from memory, with its parameter modes split out.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    pub address: usize,
+    pub opcode: OpCode,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `address` the way the machine would execute it.
+    ///
+    /// Mode digits beyond the opcode's parameters are ignored and a write target in immediate
+    /// mode is let through, since neither stops the machine from fetching the instruction.
+    /// Parameters past the end of `memory` read as zero.
+    pub fn fetch(memory: &[i64], address: usize) -> Option<Instruction> {
+        let value = *memory.get(address)?;
+        let opcode: OpCode = FromPrimitive::from_i64(value % 100)?;
+        let mut modes = value / 100;
+
+        let parameters = (1..=opcode.parameters())
+            .map(|position| {
+                let mode = FromPrimitive::from_i64(modes % 10)?;
+                modes /= 10;
+
+                Some(Parameter {
+                    mode,
+                    value: memory.get(address + position).cloned().unwrap_or(0),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Instruction {
+            address,
+            opcode,
+            parameters,
+        })
+    }
+
+    /// Decodes the instruction at `address`.
+    ///
+    /// Returns `None` unless the cells there hold a well-formed instruction: a known opcode,
+    /// valid modes for its parameters, no unused mode digits and no write in immediate mode, so
+    /// that re-encoding the result gives back exactly the same cells.
+    pub fn decode(memory: &[i64], address: usize) -> Option<Instruction> {
+        let instruction = Instruction::fetch(memory, address)?;
+        let writes_immediate = instruction.opcode.target().is_some_and(|position| {
+            instruction.parameters[position - 1].mode == ParameterMode::Immediate
+        });
+
+        if writes_immediate || instruction.encode()[0] != memory[address] {
+            return None;
+        }
+
+        Some(instruction)
+    }
+
+    /// The cells this instruction occupies in memory.
+    pub fn encode(&self) -> Vec<i64> {
+        let modes = self
+            .parameters
+            .iter()
+            .rev()
+            .fold(0, |modes, parameter| modes * 10 + parameter.mode as i64);
+
+        let mut cells = vec![modes * 100 + self.opcode as i64];
+        cells.extend(self.parameters.iter().map(|parameter| parameter.value));
+        cells
+    }
+
+    pub fn size(&self) -> usize {
+        self.opcode.size()
+    }
+
+    /// Immediate address this instruction may jump to, if it is a jump with a constant target.
+    pub fn jump_target(&self) -> Option<usize> {
+        match self.opcode {
+            OpCode::JumpIfTrue | OpCode::JumpIfNot => {
+                let target = self.parameters[1];
+
+                if target.mode == ParameterMode::Immediate && target.value >= 0 {
+                    Some(target.value as usize)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether execution can continue with the next instruction in memory.
+    pub fn falls_through(&self) -> bool {
+        match self.opcode {
+            OpCode::End => false,
+            OpCode::JumpIfTrue | OpCode::JumpIfNot => {
+                let condition = self.parameters[0];
+
+                condition.mode != ParameterMode::Immediate
+                    || (condition.value != 0) != (self.opcode == OpCode::JumpIfTrue)
+            }
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.opcode.mnemonic().to_uppercase())?;
+
+        let target = self.opcode.target();
+
+        for (i, parameter) in self.parameters.iter().enumerate() {
+            let separator = match (i, Some(i + 1) == target) {
+                (_, true) => " -> ",
+                (0, false) => " ",
+                (_, false) => ", ",
+            };
+
+            write!(f, "{}{}", separator, parameter)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Instruction;
+
+    fn decode(memory: &[i64]) -> String {
+        Instruction::decode(memory, 0)
+            .expect("Not an instruction")
+            .to_string()
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(decode(&[1201, 26, -4, 26]), "ADD [r26], #-4 -> [26]");
+        assert_eq!(decode(&[3, 9]), "IN -> [9]");
+        assert_eq!(decode(&[203, -3]), "IN -> [r-3]");
+        assert_eq!(decode(&[104, 999]), "OUT #999");
+        assert_eq!(decode(&[1105, 1, 46]), "JT #1, #46");
+        assert_eq!(decode(&[109, 10]), "ARB #10");
+        assert_eq!(decode(&[99]), "HLT");
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(Instruction::decode(&[42], 0), None);
+        assert_eq!(Instruction::decode(&[11101, 1, 1, 0], 0), None);
+        assert_eq!(Instruction::decode(&[399, 0], 0), None);
+        assert_eq!(Instruction::decode(&[1099], 0), None);
+        assert_eq!(Instruction::decode(&[-1], 0), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        for memory in [
+            vec![1201, 26, -4, 26],
+            vec![1105, 1, 46],
+            vec![3, 9],
+            vec![99],
+        ]
+        .iter()
+        {
+            let instruction = Instruction::decode(memory, 0).expect("Not an instruction");
+            assert_eq!(&instruction.encode(), memory);
+        }
+    }
+}
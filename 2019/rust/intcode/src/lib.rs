@@ -0,0 +1,45 @@
+//! Shared Intcode computer used by every 2019 puzzle that runs Intcode programs.
+//!
+//! A [`Machine`] owns a copy of a program's memory and its instruction pointer. All input and
+//! output goes through a [`Terminal`], so the same machine can talk to stdin/stdout, to a test
+//! harness, or to other machines.
+//!
+//! Besides the machine itself the crate ships the tooling around it, exposed both as modules
+//! and as subcommands of the `intcode` binary.
+//!
+//! Memory cells are `i64` by default and grow on demand: reading past the end of the program
+//! yields zero and writing past it extends memory with zeros, up to [`MEMORY_LIMIT`] cells.
+
+pub mod amplifiers;
+pub mod analysis;
+pub mod asm;
+pub mod compile;
+pub mod debugger;
+pub mod decompile;
+pub mod disasm;
+mod error;
+#[cfg(test)]
+mod examples;
+mod instruction;
+pub mod loader;
+mod machine;
+pub mod network;
+mod opcode;
+pub mod search;
+pub mod session;
+pub mod symbolic;
+mod terminal;
+pub mod trace;
+mod varint;
+pub mod watch;
+mod word;
+
+pub use error::MachineError;
+pub use instruction::{Instruction, Parameter};
+pub use machine::{DecodedInsn, Machine, MemoryWrite, Snapshot, State, Undo, MEMORY_LIMIT};
+pub use opcode::{OpCode, ParameterMode};
+pub use terminal::{AsciiTerminal, CommandLineTerminal, QueueTerminal, Terminal};
+pub use word::Word;
+
+#[cfg(feature = "bigint")]
+pub use num_bigint::BigInt;
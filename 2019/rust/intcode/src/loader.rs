@@ -0,0 +1,300 @@
+//! Reading and writing program images.
+//!
+//! Programs come as comma-separated text, as in the puzzle inputs, or as a compact binary image:
+//! the magic bytes `ICB1`, the number of values and then each value, all as LEB128 varints with
+//! values zigzag-encoded. Either may be gzip-compressed. [`decode`] tells the formats apart by
+//! their first bytes, so any of them can be given wherever a program is read.
+//!
+//! Text is forgiving about layout: values may be surrounded by spaces and newlines and the last
+//! one may be followed by a comma. A value that is not an integer is reported with its line and
+//! column.
+
+use crate::varint;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+
+/// Starts every binary image.
+const MAGIC: &[u8; 4] = b"ICB1";
+
+/// Starts every gzip stream.
+const GZIP_MAGIC: &[u8; 2] = &[0x1f, 0x8b];
+
+/// How [`encode`] writes a program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Text,
+    Binary,
+    /// A gzip-compressed binary image.
+    Gzip,
+}
+
+impl Format {
+    /// The format a file with this name is expected to hold: `.gz` for gzip, `.icb` for a
+    /// binary image and text otherwise.
+    pub fn from_path(path: &str) -> Format {
+        if path.ends_with(".gz") {
+            Format::Gzip
+        } else if path.ends_with(".icb") {
+            Format::Binary
+        } else {
+            Format::Text
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// The text at `line` and `column`, both counted from 1, is not a value. `index` is the
+    /// position of the value in the program.
+    Value {
+        line: usize,
+        column: usize,
+        index: usize,
+        text: String,
+    },
+    /// There was nothing to load.
+    Empty,
+    /// The data is neither text nor a binary image.
+    Unrecognized,
+    /// A binary image whose values do not add up.
+    Corrupt(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "{}", error),
+            LoadError::Value {
+                line,
+                column,
+                index,
+                text,
+            } if text.is_empty() => write!(
+                f,
+                "line {}, column {}: value {} is missing",
+                line, column, index
+            ),
+            LoadError::Value {
+                line,
+                column,
+                index,
+                text,
+            } => write!(
+                f,
+                "line {}, column {}: value {} `{}` is not an integer",
+                line, column, index, text
+            ),
+            LoadError::Empty => write!(f, "no program"),
+            LoadError::Unrecognized => write!(f, "not a text or binary program"),
+            LoadError::Corrupt(message) => write!(f, "corrupt image: {}", message),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> LoadError {
+        LoadError::Io(error)
+    }
+}
+
+/// Parses a comma-separated program.
+pub fn parse(text: &str) -> Result<Vec<i64>, LoadError> {
+    let mut program = Vec::new();
+    let (mut line, mut column) = (1, 1);
+    let fields: Vec<&str> = text.split(',').collect();
+
+    for (index, field) in fields.iter().enumerate() {
+        let value = field.trim();
+        let leading = &field[..field.len() - field.trim_start().len()];
+
+        // Move to where the value starts.
+        for c in leading.chars() {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        // A trailing comma leaves nothing after it, which is fine.
+        if value.is_empty() && index + 1 == fields.len() && index > 0 {
+            break;
+        }
+
+        match value.parse::<i64>() {
+            Ok(parsed) => program.push(parsed),
+            Err(_) if value.is_empty() && fields.len() == 1 => return Err(LoadError::Empty),
+            Err(_) => {
+                return Err(LoadError::Value {
+                    line,
+                    column,
+                    index,
+                    text: value.to_string(),
+                })
+            }
+        }
+
+        // Move past the value, its trailing whitespace and the comma.
+        for c in field.trim_start().chars().chain(Some(',')) {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+    }
+
+    Ok(program)
+}
+
+fn read_binary(mut input: &[u8]) -> Result<Vec<i64>, LoadError> {
+    let corrupt = |e: io::Error| LoadError::Corrupt(e.to_string());
+    let count = varint::expect_unsigned(&mut input).map_err(corrupt)?;
+    let mut program = Vec::with_capacity(count.min(input.len() as u64) as usize);
+
+    for _ in 0..count {
+        program.push(varint::expect_signed(&mut input).map_err(corrupt)?);
+    }
+
+    if !input.is_empty() {
+        return Err(LoadError::Corrupt(format!(
+            "{} bytes after the last value",
+            input.len()
+        )));
+    }
+
+    Ok(program)
+}
+
+/// Reads a program in any of the formats, telling them apart by their first bytes.
+pub fn decode(bytes: &[u8]) -> Result<Vec<i64>, LoadError> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        let mut inflated = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut inflated)?;
+
+        // Only one layer of compression is expected.
+        if inflated.starts_with(GZIP_MAGIC) {
+            return Err(LoadError::Unrecognized);
+        }
+
+        return decode(&inflated);
+    }
+
+    if let Some(image) = bytes.strip_prefix(&MAGIC[..]) {
+        return read_binary(image);
+    }
+
+    let text = std::str::from_utf8(bytes).map_err(|_| LoadError::Unrecognized)?;
+    parse(text)
+}
+
+fn write_binary(program: &[i64], out: &mut impl Write) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    varint::write_unsigned(out, program.len() as u64)?;
+
+    for &value in program {
+        varint::write_signed(out, value)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a program in `format`.
+pub fn encode(program: &[i64], format: Format, mut out: impl Write) -> io::Result<()> {
+    match format {
+        Format::Text => {
+            let values: Vec<String> = program.iter().map(|v| v.to_string()).collect();
+            writeln!(out, "{}", values.join(","))
+        }
+        Format::Binary => write_binary(program, &mut out),
+        Format::Gzip => {
+            let mut encoder = GzEncoder::new(out, Compression::best());
+            write_binary(program, &mut encoder)?;
+            encoder.finish().map(|_| ())
+        }
+    }
+}
+
+/// Loads a program from a file, or from stdin if `path` is `-`.
+pub fn load(path: &str) -> Result<Vec<i64>, LoadError> {
+    let bytes = match path {
+        "-" => {
+            let mut bytes = Vec::new();
+            io::stdin().lock().read_to_end(&mut bytes)?;
+            bytes
+        }
+        _ => fs::read(path)?,
+    };
+
+    decode(&bytes)
+}
+
+/// Loads the program named by the first command-line argument that is not a `--` flag, or
+/// parses `embedded` if there is none. Lets a day run on another input without recompiling.
+pub fn from_args(embedded: &str) -> Result<Vec<i64>, LoadError> {
+    match env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(path) => load(&path),
+        None => parse(embedded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, parse, Format, LoadError};
+
+    #[test]
+    fn text() {
+        assert_eq!(parse("1,9,10,3").unwrap(), vec![1, 9, 10, 3]);
+        assert_eq!(parse("1, 9,\n10 ,3,\n").unwrap(), vec![1, 9, 10, 3]);
+        assert_eq!(parse(" -42\n").unwrap(), vec![-42]);
+
+        let error = parse("1,2,\n  3,x4,5").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 5: value 3 `x4` is not an integer"
+        );
+
+        let error = parse("1,,2").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 3: value 1 is missing");
+
+        assert!(matches!(parse(" \n"), Err(LoadError::Empty)));
+        assert!(matches!(parse(","), Err(LoadError::Value { index: 0, .. })));
+    }
+
+    #[test]
+    fn round_trip() {
+        let program = vec![1101, -3, i64::MAX, i64::MIN, 0, 99];
+
+        for &format in &[Format::Text, Format::Binary, Format::Gzip] {
+            let mut bytes = Vec::new();
+            encode(&program, format, &mut bytes).unwrap();
+            assert_eq!(decode(&bytes).unwrap(), program, "{:?}", format);
+        }
+
+        let mut binary = Vec::new();
+        encode(&[99], Format::Binary, &mut binary).unwrap();
+        assert_eq!(binary, b"ICB1\x01\xc6\x01");
+
+        binary.push(0);
+        assert_eq!(
+            decode(&binary).unwrap_err().to_string(),
+            "corrupt image: 1 bytes after the last value"
+        );
+        assert!(matches!(decode(&binary[..5]), Err(LoadError::Corrupt(_))));
+        assert!(matches!(
+            decode(&[0xff, 0xfe]),
+            Err(LoadError::Unrecognized)
+        ));
+    }
+}
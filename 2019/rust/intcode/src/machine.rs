@@ -0,0 +1,1304 @@
+use crate::compile::Registers;
+use crate::error::MachineError;
+use crate::opcode::{OpCode, ParameterMode};
+use crate::terminal::Terminal;
+use crate::word::Word;
+use num_traits::{pow, FromPrimitive, ToPrimitive};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How many instructions run between two looks at the clock when a timeout is set.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// How many cells memory may grow to. A write beyond fails with `MemoryLimitExceeded` rather
+/// than trying to allocate memory up to its address.
+pub const MEMORY_LIMIT: usize = 1 << 24;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum State {
+    /// The instruction executed and the machine can keep going.
+    Running,
+    /// The machine is parked on an input instruction the terminal had no value for.
+    NeedsInput,
+    /// The machine reached an `End` instruction.
+    Halted,
+}
+
+/// A memory cell changed by an instruction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemoryWrite<W = i64> {
+    pub address: usize,
+    pub previous: W,
+    pub value: W,
+}
+
+/// What it takes to undo one executed instruction, see [`Machine::set_history`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Undo<W = i64> {
+    /// Where the instruction ran from.
+    pub pc: usize,
+    pub relative_base: i64,
+    pub write: Option<MemoryWrite<W>>,
+    /// The value the instruction took from the terminal, which the terminal has no way to
+    /// take back, so [`Machine::unstep`] leaves returning it to the caller.
+    pub input: Option<W>,
+    /// Memory size before the write, which may have grown it.
+    size: usize,
+}
+
+/// An instruction decoded once by the instruction cache, see [`Machine::set_cache`].
+///
+/// Only `op.parameters()` of the modes and operands are meaningful.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodedInsn<W = i64> {
+    pub op: OpCode,
+    pub modes: [ParameterMode; 3],
+    pub operands: [W; 3],
+}
+
+/// A saved machine state that [`Machine::restore`] can return to.
+///
+/// Snapshots share memory with the machine they were taken from until either side writes, so
+/// taking one is cheap regardless of memory size.
+#[derive(Clone, Debug)]
+pub struct Snapshot<W = i64> {
+    memory: Arc<Vec<W>>,
+    pc: usize,
+    relative_base: i64,
+    steps: u64,
+}
+
+impl<W> Snapshot<W> {
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
+/// A state saved by the loop detector to compare later states against.
+#[derive(Clone, Debug)]
+struct Saved<W> {
+    memory: Arc<Vec<W>>,
+    pc: usize,
+    relative_base: i64,
+    steps: u64,
+    digest: u64,
+}
+
+/// Brent's cycle detection over the states a machine lands in after a jump, see
+/// [`Machine::set_loop_detection`].
+#[derive(Clone, Debug)]
+struct LoopDetector<W> {
+    /// Hash of memory kept up to date on every write; `None` once it needs recomputing.
+    digest: Option<u64>,
+    saved: Option<Saved<W>>,
+    /// Jumps since the state was saved, and how many to wait before saving a newer one.
+    jumps: u64,
+    power: u64,
+    /// Lowest and highest pc executed since the state was saved.
+    first: usize,
+    last: usize,
+}
+
+impl<W> LoopDetector<W> {
+    fn new() -> LoopDetector<W> {
+        LoopDetector {
+            digest: None,
+            saved: None,
+            jumps: 0,
+            power: 1,
+            first: 0,
+            last: 0,
+        }
+    }
+
+    /// Forgets the saved state, since input makes the future differ from the past.
+    fn reset(&mut self) {
+        self.saved = None;
+        self.jumps = 0;
+        self.power = 1;
+    }
+}
+
+/// Contribution of one cell to the memory digest. Zero cells contribute nothing, so growing
+/// memory with zeros leaves the digest alone.
+fn mix<W: Word>(address: usize, value: &W) -> u64 {
+    if value.is_zero() {
+        return 0;
+    }
+
+    let value = value.to_i64().unwrap_or(i64::MIN) as u64;
+    let mut x = (address as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ value;
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    x ^ (x >> 33)
+}
+
+/// Whether two memories hold the same values, counting missing cells as zero.
+fn same_memory<W: Word>(a: &Arc<Vec<W>>, b: &Arc<Vec<W>>) -> bool {
+    if Arc::ptr_eq(a, b) {
+        return true;
+    }
+
+    let common = a.len().min(b.len());
+
+    a[..common] == b[..common]
+        && a[common..]
+            .iter()
+            .chain(&b[common..])
+            .all(|value| value.is_zero())
+}
+
+/// An Intcode computer with its own copy of a program's memory.
+///
+/// Memory is copy-on-write: cloning a machine, forking it or taking a snapshot shares memory
+/// until one of the copies writes to it.
+#[derive(Clone, Debug)]
+pub struct Machine<W = i64> {
+    memory: Arc<Vec<W>>,
+    pc: usize,
+    relative_base: i64,
+    steps: u64,
+    step_limit: Option<u64>,
+    deadline: Option<Instant>,
+    detector: Option<LoopDetector<W>>,
+    last_write: Option<MemoryWrite<W>>,
+    cache: Option<Vec<Option<DecodedInsn<W>>>>,
+    /// Undo log of the most recent instructions, newest last, and how many it keeps.
+    history: Option<(VecDeque<Undo<W>>, usize)>,
+}
+
+impl<W: Word> Machine<W> {
+    pub fn new(program: &[W]) -> Machine<W> {
+        Machine {
+            memory: Arc::new(program.to_vec()),
+            pc: 0,
+            relative_base: 0,
+            steps: 0,
+            step_limit: None,
+            deadline: None,
+            detector: None,
+            last_write: None,
+            cache: None,
+            history: None,
+        }
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    /// Number of instructions executed so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Makes the machine fail with `StepLimitExceeded` once it has executed `limit` instructions.
+    pub fn set_step_limit(&mut self, limit: Option<u64>) {
+        self.step_limit = limit;
+    }
+
+    pub fn step_limit(&self) -> Option<u64> {
+        self.step_limit
+    }
+
+    /// Makes the machine fail with `TimedOut` once `timeout` has passed from now.
+    ///
+    /// The deadline is on the wall clock, so time spent paused or waiting for input counts too,
+    /// though the machine only fails once it runs again. The clock is only read every thousand
+    /// or so instructions, so the machine may run a little past the deadline.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.deadline = timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    /// Turns the loop detector on or off.
+    ///
+    /// The detector compares the state the machine lands in after each jump with one saved
+    /// earlier, saving a newer one after 1, 2, 4, ... jumps. Coming back to the saved state
+    /// without having read input in between proves the machine will never get anywhere else,
+    /// and it fails with `InfiniteLoop`. States are compared by a hash of memory first and by
+    /// memory itself only when the hashes agree, so a false alarm is impossible.
+    pub fn set_loop_detection(&mut self, enabled: bool) {
+        self.detector = if enabled {
+            Some(LoopDetector::new())
+        } else {
+            None
+        };
+    }
+
+    /// Keeps an undo log of the last `capacity` instructions so that [`Machine::unstep`] can
+    /// take them back, or stops keeping one.
+    ///
+    /// Each entry holds the pc, relative base and memory write of one instruction. Anything
+    /// that changes the machine from outside, such as [`Machine::write`] or
+    /// [`Machine::restore`], clears the log, since undoing across it would not give back the
+    /// states the machine was actually in.
+    pub fn set_history(&mut self, capacity: Option<usize>) {
+        self.history = capacity.map(|capacity| (VecDeque::new(), capacity));
+    }
+
+    /// Number of instructions [`Machine::unstep`] can currently take back.
+    pub fn history(&self) -> usize {
+        self.history.as_ref().map_or(0, |(log, _)| log.len())
+    }
+
+    /// Takes back the most recently executed instruction, returning what it undid, or `None`
+    /// once the undo log is empty.
+    pub fn unstep(&mut self) -> Option<Undo<W>> {
+        let undo = self.history.as_mut()?.0.pop_back()?;
+
+        if let Some(write) = &undo.write {
+            self.poke(write.address, write.previous.clone());
+            Arc::make_mut(&mut self.memory).truncate(undo.size);
+        }
+
+        self.pc = undo.pc;
+        self.relative_base = undo.relative_base;
+        self.steps -= 1;
+        self.last_write = None;
+
+        // The saved state may be one the machine has now not reached yet.
+        if let Some(detector) = &mut self.detector {
+            detector.reset();
+        }
+
+        Some(undo)
+    }
+
+    fn remember(&mut self, pc: usize, relative_base: i64, size: usize, input: Option<W>) {
+        if let Some((log, capacity)) = &mut self.history {
+            if log.len() == *capacity {
+                log.pop_front();
+            }
+
+            if *capacity > 0 {
+                log.push_back(Undo {
+                    pc,
+                    relative_base,
+                    write: self.last_write.clone(),
+                    input,
+                    size,
+                });
+            }
+        }
+    }
+
+    fn forget_history(&mut self) {
+        if let Some((log, _)) = &mut self.history {
+            log.clear();
+        }
+    }
+
+    /// Whether a step limit, timeout or loop detector is watching the machine.
+    pub(crate) fn guarded(&self) -> bool {
+        self.step_limit.is_some() || self.deadline.is_some() || self.detector.is_some()
+    }
+
+    /// Turns the instruction cache on or off.
+    ///
+    /// With the cache on, each instruction is decoded once, the first time it runs, and decoded
+    /// again only after something writes into it. Instructions with an invalid parameter mode
+    /// are never cached, so they fail exactly as they would without the cache.
+    pub fn set_cache(&mut self, enabled: bool) {
+        self.cache = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// The cached decoding of the instruction at `pc`, if there is one.
+    pub fn cached(&self, pc: usize) -> Option<&DecodedInsn<W>> {
+        self.cache.as_ref()?.get(pc)?.as_ref()
+    }
+
+    /// The cell written by the most recently executed instruction, if it wrote one.
+    pub fn last_write(&self) -> Option<&MemoryWrite<W>> {
+        self.last_write.as_ref()
+    }
+
+    /// Memory touched so far: the program image plus anything written beyond it.
+    pub fn memory(&self) -> &[W] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut [W] {
+        self.clear_cache();
+        self.forget_digest();
+        self.forget_history();
+        Arc::make_mut(&mut self.memory).as_mut_slice()
+    }
+
+    /// Reads a cell; addresses never written to read as zero.
+    pub fn read(&self, address: usize) -> W {
+        self.memory.get(address).cloned().unwrap_or_else(W::zero)
+    }
+
+    /// Writes a cell, growing memory with zeros if the address is past the end.
+    pub fn write(&mut self, address: usize, val: W) -> Result<(), MachineError> {
+        self.writable(address)?;
+
+        // A change from outside is as good as input to the loop detector.
+        if let Some(detector) = &mut self.detector {
+            detector.reset();
+        }
+
+        self.forget_history();
+        self.poke(address, val);
+        Ok(())
+    }
+
+    /// Fails if writing to `address` would grow memory past [`MEMORY_LIMIT`].
+    fn writable(&self, address: usize) -> Result<usize, MachineError> {
+        if address < self.memory.len().max(MEMORY_LIMIT) {
+            Ok(address)
+        } else {
+            Err(MachineError::MemoryLimitExceeded {
+                pc: self.pc,
+                address,
+            })
+        }
+    }
+
+    /// Writes a cell on behalf of the program itself.
+    fn poke(&mut self, address: usize, val: W) {
+        self.invalidate(address);
+
+        if let Some(LoopDetector {
+            digest: Some(digest),
+            ..
+        }) = &mut self.detector
+        {
+            let previous = self.memory.get(address).cloned().unwrap_or_else(W::zero);
+            *digest = digest
+                .wrapping_sub(mix(address, &previous))
+                .wrapping_add(mix(address, &val));
+        }
+
+        let memory = Arc::make_mut(&mut self.memory);
+
+        if address >= memory.len() {
+            memory.resize(address + 1, W::zero());
+        }
+
+        memory[address] = val;
+    }
+
+    /// Lends memory and registers to code compiled by [`crate::compile`].
+    pub fn run_native<R>(&mut self, code: impl FnOnce(&mut Vec<W>, &mut Registers) -> R) -> R {
+        let mut registers = Registers {
+            pc: self.pc,
+            relative_base: self.relative_base,
+            steps: self.steps,
+        };
+
+        self.last_write = None;
+        self.clear_cache();
+        self.forget_digest();
+        self.forget_history();
+        let result = code(Arc::make_mut(&mut self.memory), &mut registers);
+        self.pc = registers.pc;
+        self.relative_base = registers.relative_base;
+        self.steps = registers.steps;
+        result
+    }
+
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot {
+            memory: Arc::clone(&self.memory),
+            pc: self.pc,
+            relative_base: self.relative_base,
+            steps: self.steps,
+        }
+    }
+
+    /// Puts the machine back into the state it was in when `snapshot` was taken.
+    ///
+    /// The step limit, timeout and loop detection are configuration rather than state and are
+    /// left alone.
+    pub fn restore(&mut self, snapshot: &Snapshot<W>) {
+        self.memory = Arc::clone(&snapshot.memory);
+        self.pc = snapshot.pc;
+        self.relative_base = snapshot.relative_base;
+        self.steps = snapshot.steps;
+        self.last_write = None;
+        self.clear_cache();
+        self.forget_digest();
+        self.forget_history();
+    }
+
+    /// An independent copy of this machine, paused at the same point.
+    ///
+    /// Both machines share memory until one of them writes, so branching many searches off a
+    /// common prefix only pays for the cells each branch changes.
+    pub fn fork(&self) -> Machine<W> {
+        self.clone()
+    }
+
+    pub fn terminated(&self) -> bool {
+        self.instruction()
+            .is_ok_and(|instruction| instruction % 100 == OpCode::End as i64)
+    }
+
+    /// Makes the loop detector start over after memory changed behind its back.
+    fn forget_digest(&mut self) {
+        if let Some(detector) = &mut self.detector {
+            detector.reset();
+            detector.digest = None;
+        }
+    }
+
+    /// Checks the state just jumped to against the one the loop detector saved.
+    fn landed(&mut self) -> Result<(), MachineError> {
+        let detector = match &mut self.detector {
+            Some(detector) => detector,
+            None => return Ok(()),
+        };
+        let memory = &self.memory;
+        let digest = *detector.digest.get_or_insert_with(|| {
+            memory
+                .iter()
+                .enumerate()
+                .fold(0, |digest: u64, (address, value)| {
+                    digest.wrapping_add(mix(address, value))
+                })
+        });
+
+        if let Some(saved) = &detector.saved {
+            if saved.pc == self.pc
+                && saved.relative_base == self.relative_base
+                && saved.digest == digest
+                && same_memory(&saved.memory, memory)
+            {
+                return Err(MachineError::InfiniteLoop {
+                    pc: self.pc,
+                    first: detector.first,
+                    last: detector.last,
+                    period: self.steps - saved.steps,
+                });
+            }
+        }
+
+        detector.jumps += 1;
+
+        if detector.saved.is_none() || detector.jumps == detector.power {
+            if detector.saved.is_some() {
+                detector.power *= 2;
+            }
+
+            detector.saved = Some(Saved {
+                memory: Arc::clone(memory),
+                pc: self.pc,
+                relative_base: self.relative_base,
+                steps: self.steps,
+                digest,
+            });
+            detector.jumps = 0;
+            detector.first = self.pc;
+            detector.last = self.pc;
+        }
+
+        Ok(())
+    }
+
+    fn clear_cache(&mut self) {
+        if let Some(cache) = &mut self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Drops cached instructions that cover `address`.
+    fn invalidate(&mut self, address: usize) {
+        if let Some(cache) = &mut self.cache {
+            for pc in address.saturating_sub(3)..=address {
+                let covers = match cache.get(pc) {
+                    Some(Some(insn)) => pc + insn.op.size() > address,
+                    _ => false,
+                };
+
+                if covers {
+                    cache[pc] = None;
+                }
+            }
+        }
+    }
+
+    /// The instruction at the pc from the cache, decoding and caching it on a miss. `None` if
+    /// the cache is off or the instruction cannot be cached.
+    fn decoded(&mut self) -> Option<DecodedInsn<W>> {
+        if let Some(insn) = self.cached(self.pc) {
+            return Some(insn.clone());
+        }
+
+        self.cache.as_ref()?;
+        let opcode = self.opcode().ok()?;
+        let mut insn = DecodedInsn {
+            op: opcode,
+            modes: [ParameterMode::Position; 3],
+            operands: [W::zero(), W::zero(), W::zero()],
+        };
+
+        for position in 1..=opcode.parameters() {
+            insn.modes[position - 1] = self.mode(None, position).ok()?;
+            insn.operands[position - 1] = self.read(self.pc + position);
+        }
+
+        let pc = self.pc;
+        let cache = self.cache.as_mut()?;
+
+        if pc >= cache.len() {
+            cache.resize(pc + 1, None);
+        }
+
+        cache[pc] = Some(insn.clone());
+        Some(insn)
+    }
+
+    fn instruction(&self) -> Result<i64, MachineError> {
+        self.read(self.pc)
+            .to_i64()
+            .ok_or(MachineError::ValueOutOfRange { pc: self.pc })
+    }
+
+    fn opcode(&self) -> Result<OpCode, MachineError> {
+        let value = self.instruction()?;
+
+        FromPrimitive::from_i64(value % 100)
+            .ok_or(MachineError::UnknownOpCode { pc: self.pc, value })
+    }
+
+    fn parameter(&self, insn: Option<&DecodedInsn<W>>, position: usize) -> W {
+        match insn {
+            Some(insn) => insn.operands[position - 1].clone(),
+            None => self.read(self.pc + position),
+        }
+    }
+
+    fn mode(
+        &self,
+        insn: Option<&DecodedInsn<W>>,
+        position: usize,
+    ) -> Result<ParameterMode, MachineError> {
+        if let Some(insn) = insn {
+            return Ok(insn.modes[position - 1]);
+        }
+
+        let parameter_mode = self.instruction()? / 100;
+        let digit = pow(10, position - 1);
+        let mode = (parameter_mode / digit) % 10;
+
+        FromPrimitive::from_i64(mode).ok_or(MachineError::InvalidParameterMode {
+            pc: self.pc,
+            position,
+            mode,
+        })
+    }
+
+    fn to_address(&self, val: i64) -> Result<usize, MachineError> {
+        val.to_usize().ok_or(MachineError::NegativeAddress {
+            pc: self.pc,
+            address: val,
+        })
+    }
+
+    fn address(
+        &self,
+        insn: Option<&DecodedInsn<W>>,
+        position: usize,
+    ) -> Result<usize, MachineError> {
+        let val = self
+            .parameter(insn, position)
+            .to_i64()
+            .ok_or(MachineError::ValueOutOfRange { pc: self.pc })?;
+
+        match self.mode(insn, position)? {
+            ParameterMode::Position => self.to_address(val),
+            ParameterMode::Relative => self.to_address(
+                self.relative_base
+                    .checked_add(val)
+                    .ok_or(MachineError::ValueOutOfRange { pc: self.pc })?,
+            ),
+            ParameterMode::Immediate => Err(MachineError::ImmediateWrite {
+                pc: self.pc,
+                position,
+            }),
+        }
+    }
+
+    fn get_reg(&self, insn: Option<&DecodedInsn<W>>, position: usize) -> Result<W, MachineError> {
+        match self.mode(insn, position)? {
+            ParameterMode::Immediate => Ok(self.parameter(insn, position)),
+            _ => Ok(self.read(self.address(insn, position)?)),
+        }
+    }
+
+    fn set_reg(
+        &mut self,
+        insn: Option<&DecodedInsn<W>>,
+        position: usize,
+        val: W,
+    ) -> Result<(), MachineError> {
+        let target = self.address(insn, position)?;
+        self.store(target, val)
+    }
+
+    fn store(&mut self, address: usize, value: W) -> Result<(), MachineError> {
+        self.writable(address)?;
+        self.last_write = Some(MemoryWrite {
+            address,
+            previous: self.read(address),
+            value: value.clone(),
+        });
+        self.poke(address, value);
+        Ok(())
+    }
+
+    /// Executes a single instruction.
+    ///
+    /// On error the machine stays on the failing instruction.
+    pub fn step(&mut self, term: &mut impl Terminal<W>) -> Result<State, MachineError> {
+        self.last_write = None;
+        let decoded = self.decoded();
+        let insn = decoded.as_ref();
+        let opcode = match insn {
+            Some(insn) => insn.op,
+            None => self.opcode()?,
+        };
+
+        if opcode == OpCode::End {
+            return Ok(State::Halted);
+        }
+
+        if let Some(limit) = self.step_limit {
+            if self.steps >= limit {
+                return Err(MachineError::StepLimitExceeded {
+                    pc: self.pc,
+                    steps: self.steps,
+                });
+            }
+        }
+
+        if let Some(deadline) = self.deadline {
+            if self.steps.is_multiple_of(CLOCK_INTERVAL) && Instant::now() >= deadline {
+                return Err(MachineError::TimedOut {
+                    pc: self.pc,
+                    steps: self.steps,
+                });
+            }
+        }
+
+        if let Some(detector) = &mut self.detector {
+            detector.first = detector.first.min(self.pc);
+            detector.last = detector.last.max(self.pc);
+        }
+
+        let (pc, relative_base, size) = (self.pc, self.relative_base, self.memory.len());
+        let mut input = None;
+
+        match opcode {
+            OpCode::End => unreachable!(),
+            OpCode::Add | OpCode::Multiply => {
+                let r1 = self.get_reg(insn, 1)?;
+                let r2 = self.get_reg(insn, 2)?;
+
+                let value = match opcode {
+                    OpCode::Add => r1.checked_add(&r2),
+                    OpCode::Multiply => r1.checked_mul(&r2),
+                    _ => unreachable!(),
+                };
+
+                self.set_reg(
+                    insn,
+                    3,
+                    value.ok_or(MachineError::ValueOutOfRange { pc: self.pc })?,
+                )?;
+            }
+            OpCode::Input => {
+                // Checked before taking the value, which would otherwise be lost.
+                let target = self.writable(self.address(insn, 1)?)?;
+
+                match term.input() {
+                    Some(value) => {
+                        input = Some(value.clone());
+                        self.store(target, value)?;
+                    }
+                    None => return Ok(State::NeedsInput),
+                }
+
+                if let Some(detector) = &mut self.detector {
+                    detector.reset();
+                }
+            }
+            OpCode::Output => {
+                let r1 = self.get_reg(insn, 1)?;
+                term.output(r1);
+            }
+            OpCode::JumpIfTrue | OpCode::JumpIfNot => {
+                let r1 = self.get_reg(insn, 1)?;
+                let r2 = self.get_reg(insn, 2)?;
+                let condition = !r1.is_zero();
+
+                if (opcode == OpCode::JumpIfTrue) == condition {
+                    let target = r2
+                        .to_i64()
+                        .ok_or(MachineError::ValueOutOfRange { pc: self.pc })?;
+
+                    self.pc = self.to_address(target)?;
+                    self.steps += 1;
+                    self.remember(pc, relative_base, size, input);
+                    self.landed()?;
+                    return Ok(State::Running);
+                }
+            }
+            OpCode::LessThan | OpCode::Equals => {
+                let r1 = self.get_reg(insn, 1)?;
+                let r2 = self.get_reg(insn, 2)?;
+                let condition = match opcode {
+                    OpCode::LessThan => r1 < r2,
+                    OpCode::Equals => r1 == r2,
+                    _ => unreachable!(),
+                };
+
+                self.set_reg(insn, 3, if condition { W::one() } else { W::zero() })?;
+            }
+            OpCode::AdjustRelativeBase => {
+                let r1 = self.get_reg(insn, 1)?;
+                self.relative_base = r1
+                    .to_i64()
+                    .and_then(|offset| self.relative_base.checked_add(offset))
+                    .ok_or(MachineError::ValueOutOfRange { pc: self.pc })?;
+            }
+        };
+
+        self.pc += opcode.size();
+        self.steps += 1;
+        self.remember(pc, relative_base, size, input);
+        Ok(State::Running)
+    }
+
+    /// Runs until the machine halts or needs input the terminal cannot provide yet.
+    ///
+    /// A paused machine resumes from the same input instruction on the next call.
+    pub fn resume(&mut self, term: &mut impl Terminal<W>) -> Result<State, MachineError> {
+        loop {
+            match self.step(term)? {
+                State::Running => continue,
+                state => break Ok(state),
+            }
+        }
+    }
+
+    /// Runs the program to completion; running out of input is an error.
+    pub fn execute(&mut self, term: &mut impl Terminal<W>) -> Result<(), MachineError> {
+        match self.resume(term)? {
+            State::NeedsInput => Err(MachineError::InputExhausted { pc: self.pc }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Machine, State, CLOCK_INTERVAL};
+    use crate::error::MachineError;
+    use crate::examples::{COUNTDOWN, EXAMPLES};
+    use crate::opcode::OpCode;
+    use crate::terminal::QueueTerminal;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn run(program: &[i64], inputs: &[i64]) -> Vec<i64> {
+        let mut term = QueueTerminal::new(inputs);
+        assert_eq!(Machine::new(program).execute(&mut term), Ok(()));
+        term.outputs.into_iter().collect()
+    }
+
+    #[test]
+    fn day2_programs() {
+        let examples: [(&[i64], &[i64]); 5] = [
+            (
+                &[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50],
+                &[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50],
+            ),
+            (&[1, 0, 0, 0, 99], &[2, 0, 0, 0, 99]),
+            (&[2, 3, 0, 3, 99], &[2, 3, 0, 6, 99]),
+            (&[2, 4, 4, 5, 99, 0], &[2, 4, 4, 5, 99, 9801]),
+            (
+                &[1, 1, 1, 4, 99, 5, 6, 0, 99],
+                &[30, 1, 1, 4, 2, 5, 6, 0, 99],
+            ),
+        ];
+
+        for (program, expected) in examples.iter() {
+            let mut machine = Machine::new(program);
+            machine.execute(&mut QueueTerminal::default()).unwrap();
+            assert_eq!(machine.memory(), *expected);
+        }
+    }
+
+    #[test]
+    fn multiply_program() {
+        let mut machine = Machine::new(&[1002, 4, 3, 4, 33]);
+        machine.execute(&mut QueueTerminal::default()).unwrap();
+
+        assert_eq!(machine.memory()[4], 99);
+    }
+
+    #[test]
+    fn negative_program() {
+        let mut machine = Machine::new(&[1101, 100, -1, 4, 0]);
+        machine.execute(&mut QueueTerminal::default()).unwrap();
+
+        assert_eq!(machine.memory()[4], 99);
+    }
+
+    #[test]
+    fn echo_program() {
+        assert_eq!(run(&[3, 0, 4, 0, 99], &[42]), [42]);
+    }
+
+    #[test]
+    fn compare_program() {
+        let equal_program = [3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        assert_eq!(run(&equal_program, &[8]), [1]);
+        assert_eq!(run(&equal_program, &[101]), [0]);
+
+        let lessthan_program = [3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+        assert_eq!(run(&lessthan_program, &[-100]), [1]);
+        assert_eq!(run(&lessthan_program, &[9]), [0]);
+
+        let immediateequal_program = [3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        assert_eq!(run(&immediateequal_program, &[8]), [1]);
+        assert_eq!(run(&immediateequal_program, &[7]), [0]);
+
+        let immediatelessthan_program = [3, 3, 1107, -1, 8, 3, 4, 3, 99];
+        assert_eq!(run(&immediatelessthan_program, &[-100]), [1]);
+        assert_eq!(run(&immediatelessthan_program, &[9]), [0]);
+    }
+
+    #[test]
+    fn jump_program() {
+        let position_program = [3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        assert_eq!(run(&position_program, &[0]), [0]);
+        assert_eq!(run(&position_program, &[5]), [1]);
+
+        let immediate_program = [3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        assert_eq!(run(&immediate_program, &[0]), [0]);
+        assert_eq!(run(&immediate_program, &[5]), [1]);
+
+        let ro_program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        assert_eq!(run(&ro_program, &[0]), [999]);
+        assert_eq!(run(&ro_program, &[8]), [1000]);
+        assert_eq!(run(&ro_program, &[9]), [1001]);
+    }
+
+    #[test]
+    fn relative_base_program() {
+        let quine = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_eq!(run(&quine, &[]), quine);
+
+        let relative_input = [109, 10, 203, -3, 204, -3, 99];
+        assert_eq!(run(&relative_input, &[77]), [77]);
+    }
+
+    #[test]
+    fn large_number_program() {
+        let sixteen_digits = [1102, 34915192, 34915192, 7, 4, 7, 99, 0];
+        assert_eq!(run(&sixteen_digits, &[]), [1219070632396864]);
+
+        let large_output = [104, 1125899906842624, 99];
+        assert_eq!(run(&large_output, &[]), [1125899906842624]);
+    }
+
+    #[test]
+    fn memory_growth() {
+        let mut machine = Machine::new(&[1101, 2, 3, 1000, 1001, 2000, 5, 1001, 99]);
+        machine.execute(&mut QueueTerminal::default()).unwrap();
+
+        assert_eq!(machine.read(1000), 5);
+        assert_eq!(machine.read(1001), 5);
+        assert_eq!(machine.read(5000), 0);
+        assert_eq!(machine.memory().len(), 1002);
+
+        // Rather than allocating memory all the way up to the address, the write fails.
+        assert_eq!(
+            run_error(&[1101, 1, 1, 100_000_000_000_000, 99], &[]),
+            MachineError::MemoryLimitExceeded {
+                pc: 0,
+                address: 100_000_000_000_000
+            }
+        );
+
+        let mut machine = Machine::new(&[3i64, 100_000_000_000, 99]);
+        let mut term = QueueTerminal::new(&[5]);
+        assert!(machine.execute(&mut term).is_err());
+        assert_eq!(term.inputs, [5]);
+        assert!(machine.write(super::MEMORY_LIMIT, 1).is_err());
+        assert_eq!(machine.memory().len(), 3);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint_program() {
+        use num_bigint::BigInt;
+
+        let squares: Vec<BigInt> = [2, 11, 11, 11, 2, 11, 11, 11, 4, 11, 99, 4_294_967_296]
+            .iter()
+            .map(|&x: &i64| BigInt::from(x))
+            .collect();
+
+        let mut term = QueueTerminal::default();
+        Machine::new(&squares).execute(&mut term).unwrap();
+
+        let expected = BigInt::from(4_294_967_296i64).pow(4);
+        assert_eq!(term.outputs.pop_back(), Some(expected));
+    }
+
+    fn run_error(program: &[i64], inputs: &[i64]) -> MachineError {
+        let mut term = QueueTerminal::new(inputs);
+        Machine::new(program).execute(&mut term).unwrap_err()
+    }
+
+    #[test]
+    fn machine_errors() {
+        assert_eq!(
+            run_error(&[1101, 1, 1, 5, 42, 0], &[]),
+            MachineError::UnknownOpCode { pc: 4, value: 42 }
+        );
+        assert_eq!(
+            run_error(&[304, 0, 99], &[]),
+            MachineError::InvalidParameterMode {
+                pc: 0,
+                position: 1,
+                mode: 3
+            }
+        );
+        assert_eq!(
+            run_error(&[4, -1, 99], &[]),
+            MachineError::NegativeAddress { pc: 0, address: -1 }
+        );
+        assert_eq!(
+            run_error(&[11101, 1, 1, 0, 99], &[]),
+            MachineError::ImmediateWrite { pc: 0, position: 3 }
+        );
+        assert_eq!(
+            run_error(&[3, 0, 3, 0, 99], &[7]),
+            MachineError::InputExhausted { pc: 2 }
+        );
+        assert_eq!(
+            run_error(&[1106, 0, -1], &[]),
+            MachineError::NegativeAddress { pc: 0, address: -1 }
+        );
+        assert_eq!(
+            run_error(
+                &[2, 11, 11, 11, 2, 11, 11, 11, 4, 11, 99, 4_294_967_296],
+                &[]
+            ),
+            MachineError::ValueOutOfRange { pc: 0 }
+        );
+        assert_eq!(
+            run_error(&[109, i64::MAX, 109, 1, 99], &[]),
+            MachineError::ValueOutOfRange { pc: 2 }
+        );
+    }
+
+    #[test]
+    fn step_limit() {
+        let mut machine = Machine::new(&[1105, 1, 0]);
+        machine.set_step_limit(Some(1000));
+
+        assert_eq!(
+            machine.execute(&mut QueueTerminal::default()),
+            Err(MachineError::StepLimitExceeded { pc: 0, steps: 1000 })
+        );
+        assert_eq!(machine.steps(), 1000);
+    }
+
+    #[test]
+    fn timeout() {
+        let mut machine = Machine::new(&[1105, 1, 0]);
+        machine.set_timeout(Some(Duration::from_millis(10)));
+
+        match machine.execute(&mut QueueTerminal::default()) {
+            Err(MachineError::TimedOut { pc: 0, steps }) => assert!(steps > 0),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn timeout_while_paused() {
+        // Waits for input, then spins; the wait counts against the deadline.
+        let mut machine = Machine::new(&[3, 0, 1105, 1, 2]);
+        machine.set_timeout(Some(Duration::from_millis(10)));
+        assert_eq!(
+            machine.resume(&mut QueueTerminal::default()),
+            Ok(State::NeedsInput)
+        );
+
+        std::thread::sleep(Duration::from_millis(20));
+
+        match machine.resume(&mut QueueTerminal::new(&[1])) {
+            Err(MachineError::TimedOut { steps, .. }) => assert!(steps <= CLOCK_INTERVAL),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn loop_detection() {
+        let mut machine = Machine::new(&[1105, 1, 0]);
+        machine.set_loop_detection(true);
+        assert_eq!(
+            machine.execute(&mut QueueTerminal::default()),
+            Err(MachineError::InfiniteLoop {
+                pc: 0,
+                first: 0,
+                last: 0,
+                period: 1
+            })
+        );
+
+        // Flips [7] between 0 and 1 forever, so the state repeats every other round.
+        let mut machine = Machine::new(&[1008, 7, 0, 7, 1105, 1, 0, 0]);
+        machine.set_loop_detection(true);
+        assert_eq!(
+            machine.execute(&mut QueueTerminal::default()),
+            Err(MachineError::InfiniteLoop {
+                pc: 0,
+                first: 0,
+                last: 4,
+                period: 4
+            })
+        );
+
+        // Reading the same input over and over is not a loop: more input could change things.
+        let mut machine = Machine::new(&[3, 5, 1105, 1, 0, 0]);
+        machine.set_loop_detection(true);
+        let mut term = QueueTerminal::new(&[7; 100]);
+        assert_eq!(machine.resume(&mut term), Ok(State::NeedsInput));
+
+        // Loops that end or wait for input are left alone.
+        for program in EXAMPLES {
+            let mut machine = Machine::new(program);
+            machine.set_loop_detection(true);
+            assert!(machine.resume(&mut QueueTerminal::new(&[5; 10])).is_ok());
+        }
+    }
+
+    #[test]
+    fn paused_machine_resumes() {
+        let mut machine = Machine::new(&[3, 0, 4, 0, 99]);
+        let mut term = QueueTerminal::default();
+
+        assert_eq!(machine.resume(&mut term), Ok(State::NeedsInput));
+        term.inputs.push_back(5);
+        assert_eq!(machine.resume(&mut term), Ok(State::Halted));
+        assert_eq!(term.outputs.pop_back(), Some(5));
+    }
+
+    #[test]
+    fn instruction_cache_matches_interpreter() {
+        for program in EXAMPLES {
+            for first in -1..10 {
+                let inputs: Vec<i64> = (first..first + 20).collect();
+                let mut plain = Machine::new(program);
+                let mut cached = Machine::new(program);
+                let mut plain_term = QueueTerminal::new(&inputs);
+                let mut cached_term = QueueTerminal::new(&inputs);
+                cached.set_cache(true);
+
+                assert_eq!(
+                    plain.resume(&mut plain_term),
+                    cached.resume(&mut cached_term)
+                );
+                assert_eq!(plain_term.outputs, cached_term.outputs);
+                assert_eq!(plain.memory(), cached.memory());
+                assert_eq!(plain.steps(), cached.steps());
+            }
+        }
+    }
+
+    #[test]
+    fn instruction_cache_invalidation() {
+        // Outputs 0, 1 and 2 by incrementing the operand of its own output instruction.
+        let program = [104, 0, 1001, 1, 1, 1, 1007, 1, 3, 14, 1005, 14, 0, 99, 0];
+        let mut machine = Machine::new(&program);
+        let mut term = QueueTerminal::default();
+        machine.set_cache(true);
+
+        assert_eq!(machine.execute(&mut term), Ok(()));
+        assert_eq!(term.outputs, [0, 1, 2]);
+        assert_eq!(machine.cached(0), None);
+        assert_eq!(machine.cached(2).map(|insn| insn.op), Some(OpCode::Add));
+
+        let mut machine = Machine::new(&[1101, 1, 1, 5, 104, 0, 99]);
+        machine.set_cache(true);
+        machine.step(&mut term).unwrap();
+        assert!(machine.cached(0).is_some());
+
+        machine.memory_mut()[2] = 5;
+        assert_eq!(machine.cached(0), None);
+    }
+
+    #[test]
+    fn fork_from_common_prefix() {
+        // Doubles its input after a long shared setup phase.
+        let program = [1101, 0, 21, 20, 3, 21, 1002, 21, 2, 21, 4, 21, 99];
+        let mut machine = Machine::new(&program);
+        assert_eq!(
+            machine.resume(&mut QueueTerminal::default()),
+            Ok(State::NeedsInput)
+        );
+
+        let mut forks: Vec<Machine> = (0..3).map(|_| machine.fork()).collect();
+        assert!(forks
+            .iter()
+            .all(|fork| Arc::ptr_eq(&fork.memory, &machine.memory)));
+
+        for (input, fork) in forks.iter_mut().enumerate() {
+            let mut term = QueueTerminal::new(&[input as i64 + 1]);
+            fork.execute(&mut term).unwrap();
+            assert_eq!(term.outputs, [2 * (input as i64 + 1)]);
+            assert!(!Arc::ptr_eq(&fork.memory, &machine.memory));
+        }
+
+        assert_eq!(machine.pc(), 4);
+        assert_eq!(machine.read(21), 0);
+    }
+
+    #[test]
+    fn snapshot_and_restore() {
+        let mut machine = Machine::new(&[3, 9, 1001, 9, 5, 9, 4, 9, 99, 0]);
+        let mut term = QueueTerminal::new(&[1]);
+        machine.step(&mut term).unwrap();
+
+        let snapshot = machine.snapshot();
+        machine.execute(&mut term).unwrap();
+        assert_eq!(machine.read(9), 6);
+        assert!(machine.terminated());
+
+        machine.restore(&snapshot);
+        assert_eq!(machine.pc(), 2);
+        assert_eq!(machine.steps(), 1);
+        assert_eq!(machine.read(9), 1);
+        assert_eq!(snapshot.pc(), 2);
+
+        machine.execute(&mut term).unwrap();
+        assert_eq!(term.outputs, [6, 6]);
+    }
+
+    fn thruster_output(program: &[i64], sequence: &[i64]) -> i64 {
+        sequence.iter().fold(0, |signal, &phase| {
+            *run(program, &[phase, signal]).last().expect("No output")
+        })
+    }
+
+    #[test]
+    fn amplifier_chain() {
+        let program1 = [
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        assert_eq!(thruster_output(&program1, &[4, 3, 2, 1, 0]), 43210);
+
+        let program2 = [
+            3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23,
+            99, 0, 0,
+        ];
+        assert_eq!(thruster_output(&program2, &[0, 1, 2, 3, 4]), 54321);
+
+        let program3 = [
+            3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1,
+            33, 31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
+        ];
+        assert_eq!(thruster_output(&program3, &[1, 0, 4, 3, 2]), 65210);
+    }
+
+    fn feedback_thruster_output(program: &[i64], sequence: &[i64]) -> i64 {
+        let mut amps: Vec<(Machine, QueueTerminal)> = sequence
+            .iter()
+            .map(|&phase| (Machine::new(program), QueueTerminal::new(&[phase])))
+            .collect();
+        amps[0].1.inputs.push_back(0);
+
+        while !amps.iter().all(|(machine, _)| machine.terminated()) {
+            for i in 0..amps.len() {
+                let (machine, term) = &mut amps[i];
+                machine.resume(term).unwrap();
+
+                let signals: Vec<i64> = term.outputs.drain(..).collect();
+                let next = (i + 1) % sequence.len();
+                amps[next].1.inputs.extend(signals);
+            }
+        }
+
+        amps[0].1.inputs.pop_back().expect("No output")
+    }
+
+    #[test]
+    fn amplifier_feedback_loop() {
+        let program1 = [
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        assert_eq!(
+            feedback_thruster_output(&program1, &[9, 8, 7, 6, 5]),
+            139629729
+        );
+
+        let program2 = [
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ];
+        assert_eq!(feedback_thruster_output(&program2, &[9, 7, 8, 5, 6]), 18216);
+    }
+
+    #[test]
+    fn history() {
+        let mut machine = Machine::new(COUNTDOWN);
+        let mut term = QueueTerminal::new(&[2]);
+        machine.set_history(Some(100));
+        assert_eq!(machine.resume(&mut term), Ok(State::Halted));
+        assert_eq!(machine.history(), 7);
+
+        let undo = machine.unstep().unwrap();
+        assert_eq!((undo.pc, undo.write), (8, None));
+        assert_eq!((machine.pc(), machine.steps()), (8, 6));
+
+        let undo = machine.unstep().unwrap();
+        assert_eq!(undo.write.as_ref().map(|write| write.previous), Some(1));
+        assert_eq!(machine.read(12), 1);
+
+        let mut first = undo;
+        while let Some(undo) = machine.unstep() {
+            first = undo;
+        }
+
+        assert_eq!(first.input, Some(2));
+        assert_eq!(machine.memory(), COUNTDOWN);
+        assert_eq!((machine.pc(), machine.steps()), (0, 0));
+
+        // Running forward again retraces the same states.
+        let mut term = QueueTerminal::new(&[2]);
+        assert_eq!(machine.resume(&mut term), Ok(State::Halted));
+        assert_eq!(term.outputs, [2, 1]);
+
+        machine.set_history(Some(2));
+        machine.restore(&Machine::new(COUNTDOWN).snapshot());
+        machine.resume(&mut QueueTerminal::new(&[2])).unwrap();
+        assert_eq!(machine.history(), 2);
+
+        machine.write(0, 4).unwrap();
+        assert_eq!(machine.history(), 0);
+        assert_eq!(machine.unstep(), None);
+    }
+}
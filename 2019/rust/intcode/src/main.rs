@@ -0,0 +1,290 @@
+use intcode::analysis::analyze;
+use intcode::asm::assemble_with;
+use intcode::debugger::Debugger;
+use intcode::decompile::decompile;
+use intcode::disasm::disassemble;
+use intcode::loader::{self, Format};
+use intcode::search::{Patch, Run, Search};
+use intcode::session::{replay, Recorder, Session};
+use intcode::trace::{TraceFormat, Tracer};
+use intcode::watch::{Heatmap, Watcher};
+use intcode::{AsciiTerminal, CommandLineTerminal, Machine, QueueTerminal};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "\
+Usage: intcode <command> <file> [args...]
+
+<file> is a comma-separated program, a binary image or either gzipped, or - for stdin.
+
+Commands:
+    analyze                     Summarize blocks, loops, data and self-modifying writes
+    ascii [script]              Run the program as a text adventure on stdin/stdout, typing
+                                the lines of <script> first
+    asm                         Assemble a source file and print the program; includes
+                                other than \"std\" are found next to the file
+    cfg                         Print the control-flow graph in Graphviz DOT
+    convert <out>               Write the program to <out>, gzipped if it ends in .gz, as a
+                                binary image if it ends in .icb and as text otherwise
+    debug                       Step through the program interactively
+    decompile                   Print the program as structured pseudo-code
+    disasm                      Print an annotated listing of the program
+    heatmap [input...]          Run the program and show how often it read and wrote each
+                                memory cell
+    heatmap-png <out> [input...]
+                                Like heatmap, drawing the cells into the PNG <out> instead
+                                (needs the png feature)
+    profile [input...]          Run the program and summarize what it executed
+    record <session>            Run the program on stdin/stdout and save everything it reads
+                                and writes to <session>
+    replay <session>            Run the program on the inputs saved in <session> and report
+                                where its outputs differ
+    search <goal> <patch>...    Try every combination of patched values and print those
+                                reaching <goal>, either <addr>=<value> for a memory cell
+                                or out=<value> for the last output; each patch is
+                                <addr>=<lo>..<hi> or in<slot>=<lo>..<hi>
+    trace <out> [input...]      Run the program and write every instruction to <out>,
+                                as JSON lines if it ends in .jsonl and binary otherwise";
+
+fn load(path: &str) -> Result<Vec<i64>, String> {
+    loader::load(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn parse_inputs(args: &[String]) -> Result<Vec<i64>, String> {
+    args.iter()
+        .map(|arg| {
+            arg.parse::<i64>()
+                .map_err(|_| format!("input `{}` is not an integer", arg))
+        })
+        .collect()
+}
+
+fn run_traced(path: &str, inputs: &[String], mut tracer: Tracer) -> Result<(), String> {
+    let mut machine = Machine::new(&load(path)?);
+    let mut terminal = QueueTerminal::new(&parse_inputs(inputs)?);
+    let result = tracer.execute(&mut machine, &mut terminal);
+
+    for value in terminal.outputs {
+        println!("{}", value);
+    }
+
+    let profile = tracer.finish().map_err(|e| e.to_string())?;
+    eprint!("{}", profile);
+    result.map_err(|e| e.to_string())
+}
+
+fn run_watched(path: &str, inputs: &[String]) -> Result<Heatmap, String> {
+    let mut machine = Machine::new(&load(path)?);
+    let mut terminal = QueueTerminal::new(&parse_inputs(inputs)?);
+    let mut watcher = Watcher::new();
+    let result = watcher.resume(&mut machine, &mut terminal);
+
+    for value in terminal.outputs {
+        println!("{}", value);
+    }
+
+    result.map_err(|e| e.to_string())?;
+    Ok(watcher.heatmap)
+}
+
+/// Most instructions one run of a search may execute before it counts as stuck.
+const SEARCH_STEP_LIMIT: u64 = 1_000_000;
+
+fn parse_value(text: &str) -> Result<i64, String> {
+    text.parse::<i64>()
+        .map_err(|_| format!("`{}` is not an integer", text))
+}
+
+fn parse_patch(arg: &str) -> Result<(Patch, RangeInclusive<i64>), String> {
+    let bad = || {
+        format!(
+            "patch `{}` is not <addr>=<lo>..<hi> or in<slot>=<lo>..<hi>",
+            arg
+        )
+    };
+    let (place, range) = arg.split_once('=').ok_or_else(bad)?;
+    let (lo, hi) = range.split_once("..").ok_or_else(bad)?;
+    let patch = match place.strip_prefix("in") {
+        Some(slot) => Patch::Input(slot.parse().map_err(|_| bad())?),
+        None => Patch::Memory(place.parse().map_err(|_| bad())?),
+    };
+
+    Ok((patch, parse_value(lo)?..=parse_value(hi)?))
+}
+
+/// Whether a run of a search reached what it was looking for.
+type Goal = Box<dyn Fn(&Run) -> bool + Sync>;
+
+fn parse_goal(arg: &str) -> Result<Goal, String> {
+    let bad = || format!("goal `{}` is not <addr>=<value> or out=<value>", arg);
+    let (place, value) = arg.split_once('=').ok_or_else(bad)?;
+    let value = parse_value(value)?;
+
+    if place == "out" {
+        return Ok(Box::new(move |run| {
+            run.result.is_ok() && run.outputs.last() == Some(&value)
+        }));
+    }
+
+    let address: usize = place.parse().map_err(|_| bad())?;
+    Ok(Box::new(move |run| {
+        run.result.is_ok() && run.memory.get(address).cloned().unwrap_or(0) == value
+    }))
+}
+
+fn search(path: &str, goal: &str, patches: &[String]) -> Result<(), String> {
+    let program = load(path)?;
+    let goal = parse_goal(goal)?;
+    let patches = patches
+        .iter()
+        .map(|arg| parse_patch(arg))
+        .collect::<Result<Vec<_>, String>>()?;
+    let search = patches
+        .iter()
+        .cloned()
+        .fold(Search::new(&program), |search, (patch, range)| {
+            search.patch(patch, range)
+        })
+        .configure(|machine| machine.set_step_limit(Some(SEARCH_STEP_LIMIT)))
+        .progress(|progress| eprint!("\r{}", progress));
+
+    let found = search.all(goal);
+    eprintln!();
+
+    for run in found {
+        let values: Vec<String> = patches
+            .iter()
+            .zip(&run.values)
+            .map(|((patch, _), value)| format!("{}={}", patch, value))
+            .collect();
+        println!("{}", values.join(" "));
+    }
+
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args {
+        [command, path] if command == "decompile" => {
+            print!("{}", decompile(&load(path)?));
+            Ok(())
+        }
+        [command, path] if command == "disasm" => {
+            print!("{}", disassemble(&load(path)?));
+            Ok(())
+        }
+        [command, path] if command == "analyze" => {
+            print!("{}", analyze(&load(path)?));
+            Ok(())
+        }
+        [command, path] if command == "cfg" => {
+            print!("{}", analyze(&load(path)?).to_dot());
+            Ok(())
+        }
+        [command, path, out] if command == "convert" => {
+            let program = load(path)?;
+            let file = File::create(out).map_err(|e| format!("{}: {}", out, e))?;
+
+            loader::encode(&program, Format::from_path(out), BufWriter::new(file))
+                .map_err(|e| format!("{}: {}", out, e))
+        }
+        [command, path] if command == "asm" => {
+            let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+            let include = |name: &str| {
+                let file = directory.join(name);
+                fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))
+            };
+            let program =
+                assemble_with(&source, include).map_err(|e| format!("{}: {}", path, e))?;
+            let values: Vec<String> = program.iter().map(|v| v.to_string()).collect();
+
+            println!("{}", values.join(","));
+            Ok(())
+        }
+        [command, path, script @ ..] if command == "ascii" && script.len() <= 1 => {
+            let mut machine = Machine::new(&load(path)?);
+            let stdin = io::stdin();
+            let mut terminal = AsciiTerminal::new(stdin.lock(), io::stdout());
+
+            if let Some(script) = script.first() {
+                terminal
+                    .script(&fs::read_to_string(script).map_err(|e| format!("{}: {}", script, e))?);
+            }
+
+            let result = machine.execute(&mut terminal);
+            terminal.flush();
+            result.map_err(|e| e.to_string())
+        }
+        [command, path] if command == "debug" => {
+            let stdin = io::stdin();
+
+            Debugger::new(&load(path)?)
+                .repl(stdin.lock(), io::stdout())
+                .map_err(|e| e.to_string())
+        }
+        [command, path, inputs @ ..] if command == "heatmap" => {
+            eprint!("{}", run_watched(path, inputs)?);
+            Ok(())
+        }
+        #[cfg(feature = "png")]
+        [command, path, out, inputs @ ..] if command == "heatmap-png" => {
+            let heatmap = run_watched(path, inputs)?;
+            let file = File::create(out).map_err(|e| format!("{}: {}", out, e))?;
+
+            heatmap
+                .write_png(50, 8, BufWriter::new(file))
+                .map_err(|e| format!("{}: {}", out, e))
+        }
+        [command, path, inputs @ ..] if command == "profile" => {
+            run_traced(path, inputs, Tracer::new())
+        }
+        [command, path, session] if command == "record" => {
+            let mut recorder = Recorder::new(CommandLineTerminal {});
+            let result = Machine::new(&load(path)?).resume(&mut recorder);
+
+            fs::write(session, recorder.session.to_string())
+                .map_err(|e| format!("{}: {}", session, e))?;
+            result.map(|_| ()).map_err(|e| e.to_string())
+        }
+        [command, path, session] if command == "replay" => {
+            let text = fs::read_to_string(session).map_err(|e| format!("{}: {}", session, e))?;
+            let recorded: Session = text.parse().map_err(|e| format!("{}: {}", session, e))?;
+
+            replay(&mut Machine::new(&load(path)?), &recorded).map_err(|e| e.to_string())?;
+            println!("replayed {} events", recorded.events.len());
+            Ok(())
+        }
+        [command, path, goal, patches @ ..] if command == "search" && !patches.is_empty() => {
+            search(path, goal, patches)
+        }
+        [command, path, out, inputs @ ..] if command == "trace" => {
+            let format = if out.ends_with(".jsonl") {
+                TraceFormat::Jsonl
+            } else {
+                TraceFormat::Binary
+            };
+            let file = File::create(out).map_err(|e| format!("{}: {}", out, e))?;
+
+            run_traced(
+                path,
+                inputs,
+                Tracer::with_output(format, BufWriter::new(file)),
+            )
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(message) = run(&args) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
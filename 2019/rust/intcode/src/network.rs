@@ -0,0 +1,790 @@
+//! Networks of machines connected by channels.
+//!
+//! Every node of a [`Network`] is a [`Machine`] with an input queue and a [`Route`] that says
+//! where its output goes: straight into other nodes' queues (chains, rings and fan-out), into
+//! the node addressed by the first value of each fixed-size packet, or out of the network.
+//!
+//! The same network can be run cooperatively on the calling thread or with one thread per node
+//! talking over `std::sync::mpsc` channels. Either way a run ends when every machine has
+//! halted, when the network deadlocks with machines waiting for input nobody will send, or,
+//! for nodes that are given an idle input instead of pausing, when the network goes idle.
+
+use crate::compile::{self, Native};
+use crate::error::MachineError;
+use crate::machine::{Machine, State};
+use crate::terminal::Terminal;
+use crate::trace::Tracer;
+use crate::watch::{self, Watcher};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// How many instructions a node runs before the cooperative scheduler moves on, so that a node
+/// busy computing cannot starve the others.
+const SLICE: usize = 1000;
+
+/// How many instructions a threaded node runs between two looks at its messages, so that a node
+/// busy computing still notices when the network stops.
+const POLL_INTERVAL: u64 = 1024;
+
+/// Where a node's output goes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Route {
+    /// Every value is copied to each of these nodes.
+    Nodes(Vec<usize>),
+    /// Output is split into packets of `width` values, the first of which is the address of the
+    /// node that receives the rest. Packets for addresses outside the network end up in
+    /// [`Network::packets`].
+    Packets { width: usize },
+    /// Every value ends up in [`Network::outputs`].
+    Outside,
+}
+
+/// A packet addressed to something outside the network.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Packet {
+    pub source: usize,
+    pub address: i64,
+    pub values: Vec<i64>,
+}
+
+/// Why a run ended.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// Every machine halted.
+    Halted,
+    /// Nothing moved and at least one node is polling with its idle input.
+    Idle,
+    /// Nothing moved and every machine that has not halted is waiting for input.
+    Deadlock,
+}
+
+/// Why a network could not run to the end.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NetworkError {
+    /// A machine in the network failed.
+    Failed { node: usize, error: MachineError },
+    /// A node routes to a node that is not in the network, or into packets of no values.
+    BadRoute { node: usize, route: Route },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::Failed { node, error } => write!(f, "node {}: {}", node, error),
+            NetworkError::BadRoute {
+                node,
+                route: Route::Packets { .. },
+            } => write!(f, "node {} has empty packets", node),
+            NetworkError::BadRoute { node, .. } => {
+                write!(f, "node {} routes to a missing node", node)
+            }
+        }
+    }
+}
+
+impl Error for NetworkError {}
+
+pub struct Node {
+    pub machine: Machine,
+    pub inputs: VecDeque<i64>,
+    /// Handed to the machine when its queue is empty instead of pausing it.
+    pub idle_input: Option<i64>,
+}
+
+enum Delivery {
+    Node(usize, Vec<i64>),
+    Output(usize, i64),
+    Packet(Packet),
+}
+
+/// The routing half of a network, kept apart from the nodes so the threaded scheduler can
+/// route while every node is lent to its own thread.
+#[derive(Default)]
+struct Links {
+    routes: Vec<Route>,
+    partial: Vec<Vec<i64>>,
+    last: Vec<Option<i64>>,
+}
+
+impl Links {
+    fn route(&mut self, source: usize, value: i64) -> Vec<Delivery> {
+        self.last[source] = Some(value);
+
+        match &self.routes[source] {
+            Route::Nodes(targets) => targets
+                .iter()
+                .map(|&target| Delivery::Node(target, vec![value]))
+                .collect(),
+            Route::Packets { width } => {
+                let partial = &mut self.partial[source];
+                partial.push(value);
+
+                if partial.len() < *width {
+                    return Vec::new();
+                }
+
+                let values = partial.split_off(1);
+                let address = partial.pop().expect("Empty packet");
+
+                if address >= 0 && (address as usize) < self.routes.len() {
+                    vec![Delivery::Node(address as usize, values)]
+                } else {
+                    vec![Delivery::Packet(Packet {
+                        source,
+                        address,
+                        values,
+                    })]
+                }
+            }
+            Route::Outside => vec![Delivery::Output(source, value)],
+        }
+    }
+}
+
+enum Message {
+    Values(Vec<i64>),
+    Stop,
+}
+
+enum Event {
+    Output(usize, i64),
+    /// The node is blocked on input after taking `received` messages.
+    Waiting(usize, u64),
+    /// The node started polling with its idle input after taking `received` messages.
+    Starved(usize, u64),
+    Halted(usize),
+    Failed(usize, MachineError),
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Status {
+    Active,
+    Waiting(u64),
+    Starved(u64),
+    Halted,
+}
+
+#[derive(Eq, PartialEq)]
+enum Slice {
+    Busy,
+    Waiting,
+    Starved,
+    Halted,
+}
+
+/// The terminal a node's machine sees: its input queue, refilled from a channel when running
+/// threaded.
+struct Port<'a> {
+    inputs: &'a mut VecDeque<i64>,
+    idle_input: Option<i64>,
+    receiver: Option<&'a Receiver<Message>>,
+    outputs: Vec<i64>,
+    received: bool,
+    starved: bool,
+    messages: u64,
+    stopped: bool,
+}
+
+impl<'a> Port<'a> {
+    fn new(
+        inputs: &'a mut VecDeque<i64>,
+        idle_input: Option<i64>,
+        receiver: Option<&'a Receiver<Message>>,
+    ) -> Port<'a> {
+        Port {
+            inputs,
+            idle_input,
+            receiver,
+            outputs: Vec::new(),
+            received: false,
+            starved: false,
+            messages: 0,
+            stopped: false,
+        }
+    }
+
+    fn take(&mut self, message: Message) {
+        match message {
+            Message::Values(values) => {
+                self.inputs.extend(values);
+                self.messages += 1;
+            }
+            Message::Stop => self.stopped = true,
+        }
+    }
+}
+
+impl<'a> Terminal for Port<'a> {
+    fn input(&mut self) -> Option<i64> {
+        if let (true, Some(receiver)) = (self.inputs.is_empty(), self.receiver) {
+            while !self.stopped {
+                match receiver.try_recv() {
+                    Ok(message) => self.take(message),
+                    Err(_) => break,
+                }
+            }
+        }
+
+        match (self.inputs.pop_front(), self.idle_input) {
+            (Some(value), _) => {
+                self.received = true;
+                Some(value)
+            }
+            (None, Some(idle)) if !self.stopped => {
+                self.starved = true;
+                Some(idle)
+            }
+            (None, _) => None,
+        }
+    }
+
+    fn output(&mut self, val: i64) {
+        self.outputs.push(val);
+    }
+}
+
+#[derive(Default)]
+pub struct Network {
+    nodes: Vec<Node>,
+    links: Links,
+    /// Values written by nodes routed [`Route::Outside`], with the node that wrote them.
+    pub outputs: VecDeque<(usize, i64)>,
+    /// Packets addressed to something other than a node.
+    pub packets: VecDeque<Packet>,
+}
+
+impl Network {
+    pub fn new() -> Network {
+        Network::default()
+    }
+
+    /// Copies of `program` connected in a ring, each given its setting as first input.
+    pub fn ring(program: &[i64], settings: &[i64]) -> Network {
+        let mut network = Network::new();
+
+        for (i, &setting) in settings.iter().enumerate() {
+            let next = (i + 1) % settings.len();
+            let node = network.add(Machine::new(program), Route::Nodes(vec![next]));
+            network.send(node, &[setting]);
+        }
+
+        network
+    }
+
+    /// Adds a node and returns its index, which is also its packet address.
+    pub fn add(&mut self, machine: Machine, route: Route) -> usize {
+        self.nodes.push(Node {
+            machine,
+            inputs: VecDeque::new(),
+            idle_input: None,
+        });
+        self.links.routes.push(route);
+        self.links.partial.push(Vec::new());
+        self.links.last.push(None);
+        self.nodes.len() - 1
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn node_mut(&mut self, node: usize) -> &mut Node {
+        &mut self.nodes[node]
+    }
+
+    /// The last value a node wrote, wherever it was routed.
+    pub fn last_output(&self, node: usize) -> Option<i64> {
+        self.links.last.get(node).copied().flatten()
+    }
+
+    /// Queues input for a node.
+    pub fn send(&mut self, node: usize, values: &[i64]) {
+        self.nodes[node].inputs.extend(values.iter().cloned());
+    }
+
+    /// Checks that every route leads somewhere, which the run methods do before they start.
+    pub fn check_routes(&self) -> Result<(), NetworkError> {
+        for (node, route) in self.links.routes.iter().enumerate() {
+            let bad = match route {
+                Route::Nodes(targets) => targets.iter().any(|&target| target >= self.nodes.len()),
+                Route::Packets { width } => *width == 0,
+                Route::Outside => false,
+            };
+
+            if bad {
+                return Err(NetworkError::BadRoute {
+                    node,
+                    route: route.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn deliver(&mut self, delivery: Delivery) {
+        match delivery {
+            Delivery::Node(node, values) => self.nodes[node].inputs.extend(values),
+            Delivery::Output(node, value) => self.outputs.push_back((node, value)),
+            Delivery::Packet(packet) => self.packets.push_back(packet),
+        }
+    }
+
+    /// Runs every node on this thread, taking turns, until the network halts, deadlocks or
+    /// goes idle.
+    pub fn run(&mut self) -> Result<Outcome, NetworkError> {
+        self.run_with(|_, machine, port| machine.step(port))
+    }
+
+    /// Like [`Network::run`], but running every node in `native`'s compiled code where it
+    /// can, so every node must have been loaded with `N::PROGRAM`. Nodes with an idle input are
+    /// interpreted, since compiled code polling it would never give control back.
+    pub fn run_compiled<N: Native>(&mut self, native: &N) -> Result<Outcome, NetworkError> {
+        let mut modified = vec![false; self.nodes.len()];
+
+        self.run_with(|node, machine, port| match port.idle_input {
+            Some(_) => machine.step(port),
+            None => compile::resume(native, machine, &mut modified[node], port),
+        })
+    }
+
+    /// Like [`Network::run`], tracing every instruction of every node.
+    pub fn run_traced(&mut self, tracer: &mut Tracer) -> Result<Outcome, NetworkError> {
+        self.run_with(|_, machine, port| tracer.step(machine, port))
+    }
+
+    /// Like [`Network::run`], counting and watching the memory accesses of every node. A network
+    /// cannot pause, so pausing watchpoints are logged like the rest.
+    pub fn run_watched(&mut self, watcher: &mut Watcher) -> Result<Outcome, NetworkError> {
+        self.run_with(|_, machine, port| match watcher.step(machine, port)? {
+            watch::Stop::State(state) => Ok(state),
+            watch::Stop::Paused(hit) => {
+                watcher.log.push(hit);
+                Ok(State::Running)
+            }
+        })
+    }
+
+    fn run_with(
+        &mut self,
+        mut step: impl FnMut(usize, &mut Machine, &mut Port) -> Result<State, MachineError>,
+    ) -> Result<Outcome, NetworkError> {
+        self.check_routes()?;
+
+        loop {
+            let mut slices = Vec::with_capacity(self.nodes.len());
+
+            for node in 0..self.nodes.len() {
+                slices.push(self.slice(node, &mut step)?);
+            }
+
+            if slices.contains(&Slice::Busy) {
+                continue;
+            } else if slices.iter().all(|slice| *slice == Slice::Halted) {
+                break Ok(Outcome::Halted);
+            } else if slices.contains(&Slice::Starved) {
+                break Ok(Outcome::Idle);
+            } else {
+                break Ok(Outcome::Deadlock);
+            }
+        }
+    }
+
+    /// Runs one node until it blocks, polls with its idle input or uses up its slice. The node
+    /// counts as busy if it took input or wrote output along the way.
+    fn slice(
+        &mut self,
+        index: usize,
+        step: &mut impl FnMut(usize, &mut Machine, &mut Port) -> Result<State, MachineError>,
+    ) -> Result<Slice, NetworkError> {
+        let mut busy = false;
+
+        for _ in 0..SLICE {
+            let node = &mut self.nodes[index];
+            let mut port = Port::new(&mut node.inputs, node.idle_input, None);
+            let state = step(index, &mut node.machine, &mut port)
+                .map_err(|error| NetworkError::Failed { node: index, error })?;
+            let Port {
+                outputs,
+                received,
+                starved,
+                ..
+            } = port;
+
+            busy |= received || !outputs.is_empty();
+
+            for value in outputs {
+                for delivery in self.links.route(index, value) {
+                    self.deliver(delivery);
+                }
+            }
+
+            let slice = match state {
+                State::Running if starved => Slice::Starved,
+                State::Running => continue,
+                State::NeedsInput => Slice::Waiting,
+                State::Halted => Slice::Halted,
+            };
+
+            return Ok(if busy { Slice::Busy } else { slice });
+        }
+
+        Ok(Slice::Busy)
+    }
+
+    /// Runs every node on its own thread until the network halts, deadlocks or goes idle.
+    ///
+    /// Output is routed through the calling thread, which counts the messages sent to each
+    /// node so it can tell a node that is really waiting from one whose input is in flight.
+    pub fn run_threaded(&mut self) -> Result<Outcome, NetworkError> {
+        self.check_routes()?;
+
+        let (event_sender, events) = mpsc::channel();
+        let Network {
+            nodes,
+            links,
+            outputs,
+            packets,
+        } = self;
+
+        thread::scope(|scope| {
+            let mut senders = Vec::with_capacity(nodes.len());
+
+            for (index, node) in nodes.iter_mut().enumerate() {
+                let (sender, receiver) = mpsc::channel();
+                let events = event_sender.clone();
+                senders.push(sender);
+                scope.spawn(move || work(index, node, receiver, events));
+            }
+
+            let mut sent = vec![0; senders.len()];
+            let mut status = vec![Status::Active; senders.len()];
+            let mut result = None;
+
+            while result.is_none() {
+                match events.recv().expect("Worker hung up") {
+                    Event::Output(node, value) => {
+                        status[node] = Status::Active;
+
+                        for delivery in links.route(node, value) {
+                            match delivery {
+                                Delivery::Node(target, values) => {
+                                    sent[target] += 1;
+                                    let _ = senders[target].send(Message::Values(values));
+                                }
+                                Delivery::Output(node, value) => outputs.push_back((node, value)),
+                                Delivery::Packet(packet) => packets.push_back(packet),
+                            }
+                        }
+                    }
+                    Event::Waiting(node, received) => status[node] = Status::Waiting(received),
+                    Event::Starved(node, received) => status[node] = Status::Starved(received),
+                    Event::Halted(node) => status[node] = Status::Halted,
+                    Event::Failed(node, error) => {
+                        result = Some(Err(NetworkError::Failed { node, error }))
+                    }
+                }
+
+                let quiet = status
+                    .iter()
+                    .zip(&sent)
+                    .all(|(status, &sent)| match status {
+                        Status::Active => false,
+                        Status::Waiting(received) | Status::Starved(received) => *received == sent,
+                        Status::Halted => true,
+                    });
+
+                if quiet && result.is_none() {
+                    result = Some(Ok(if status.iter().all(|s| *s == Status::Halted) {
+                        Outcome::Halted
+                    } else if status.iter().any(|s| matches!(s, Status::Starved(_))) {
+                        Outcome::Idle
+                    } else {
+                        Outcome::Deadlock
+                    }));
+                }
+            }
+
+            for sender in senders {
+                let _ = sender.send(Message::Stop);
+            }
+
+            result.expect("No result")
+        })
+    }
+}
+
+/// Body of a node's thread in [`Network::run_threaded`]. Runs until told to stop, keeping any
+/// input that arrives after the machine halts or fails in the node's queue.
+fn work(index: usize, node: &mut Node, receiver: Receiver<Message>, events: Sender<Event>) {
+    let mut messages = 0;
+    let mut announced = false;
+    let mut stopped = false;
+    let mut steps: u64 = 0;
+
+    while !stopped {
+        let mut port = Port::new(&mut node.inputs, node.idle_input, Some(&receiver));
+        let result = node.machine.step(&mut port);
+        let Port {
+            outputs,
+            received,
+            starved,
+            messages: taken,
+            stopped: stop,
+            ..
+        } = port;
+        messages += taken;
+        stopped = stop;
+        announced &= !received && outputs.is_empty();
+
+        for value in outputs {
+            let _ = events.send(Event::Output(index, value));
+        }
+
+        match result {
+            Ok(State::Running) if starved && !announced => {
+                announced = true;
+                let _ = events.send(Event::Starved(index, messages));
+            }
+            Ok(State::Running) if starved => thread::yield_now(),
+            Ok(State::Running) => {}
+            Ok(State::NeedsInput) if !stopped => {
+                let _ = events.send(Event::Waiting(index, messages));
+
+                match receiver.recv() {
+                    Ok(Message::Values(values)) => {
+                        node.inputs.extend(values);
+                        messages += 1;
+                    }
+                    Ok(Message::Stop) | Err(_) => stopped = true,
+                }
+            }
+            Ok(State::NeedsInput) => {}
+            Ok(State::Halted) => {
+                let _ = events.send(Event::Halted(index));
+                break;
+            }
+            Err(error) => {
+                let _ = events.send(Event::Failed(index, error));
+                break;
+            }
+        }
+
+        // Input is only read from the channel when the machine asks for it, so a node that
+        // never does would not hear that another one failed and the network is stopping.
+        steps += 1;
+
+        if steps.is_multiple_of(POLL_INTERVAL) {
+            while !stopped {
+                match receiver.try_recv() {
+                    Ok(Message::Values(values)) => {
+                        node.inputs.extend(values);
+                        messages += 1;
+                    }
+                    Ok(Message::Stop) => stopped = true,
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+
+    if !stopped {
+        while let Ok(Message::Values(values)) = receiver.recv() {
+            node.inputs.extend(values);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Network, NetworkError, Outcome, Packet, Route};
+    use crate::asm::assemble;
+    use crate::error::MachineError;
+    use crate::machine::Machine;
+
+    const FEEDBACK_LOOP: [i64; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    /// Reads its address, then forwards every value it receives to the next address plus one.
+    const RELAY: &str = "
+        in -> [id]
+    loop:
+        in -> [x]
+        eq [x], #-1 -> [t]
+        jt [t], #loop
+        add [id], #1 -> [next]
+        out [next]
+        add [x], #1 -> [x]
+        out [x]
+        jt #1, #loop
+    id: .data 0
+    x: .data 0
+    t: .data 0
+    next: .data 0
+    ";
+
+    fn both(network: impl Fn() -> Network, check: impl Fn(Network, bool)) {
+        check(network(), false);
+        check(network(), true);
+    }
+
+    fn run(network: &mut Network, threaded: bool) -> Result<Outcome, NetworkError> {
+        if threaded {
+            network.run_threaded()
+        } else {
+            network.run()
+        }
+    }
+
+    #[test]
+    fn feedback_ring() {
+        both(
+            || {
+                let mut network = Network::ring(&FEEDBACK_LOOP, &[9, 8, 7, 6, 5]);
+                network.send(0, &[0]);
+                network
+            },
+            |mut network, threaded| {
+                assert_eq!(run(&mut network, threaded), Ok(Outcome::Halted));
+                assert_eq!(network.nodes()[0].inputs, [139629729]);
+                assert_eq!(network.last_output(4), Some(139629729));
+            },
+        );
+    }
+
+    #[test]
+    fn fan_out() {
+        let double = [3, 9, 102, 2, 9, 9, 4, 9, 99, 0];
+
+        both(
+            || {
+                let mut network = Network::new();
+                network.add(Machine::new(&[3, 0, 4, 0, 99]), Route::Nodes(vec![1, 2]));
+                network.add(Machine::new(&double), Route::Outside);
+                network.add(Machine::new(&double), Route::Nodes(vec![3]));
+                network.add(Machine::new(&double), Route::Outside);
+                network.send(0, &[21]);
+                network
+            },
+            |mut network, threaded| {
+                assert_eq!(run(&mut network, threaded), Ok(Outcome::Halted));
+
+                let mut outputs: Vec<(usize, i64)> = network.outputs.into_iter().collect();
+                outputs.sort();
+                assert_eq!(outputs, [(1, 42), (3, 84)]);
+            },
+        );
+    }
+
+    #[test]
+    fn idle_packet_network() {
+        let program = assemble(RELAY).unwrap();
+
+        both(
+            || {
+                let mut network = Network::new();
+
+                for address in 0..3 {
+                    let node = network.add(Machine::new(&program), Route::Packets { width: 2 });
+                    network.node_mut(node).idle_input = Some(-1);
+                    network.send(node, &[address]);
+                }
+
+                network.send(0, &[5]);
+                network
+            },
+            |mut network, threaded| {
+                assert_eq!(run(&mut network, threaded), Ok(Outcome::Idle));
+                assert_eq!(
+                    network.packets.pop_front(),
+                    Some(Packet {
+                        source: 2,
+                        address: 3,
+                        values: vec![8],
+                    })
+                );
+
+                network.send(1, &[10]);
+                assert_eq!(run(&mut network, threaded), Ok(Outcome::Idle));
+                assert_eq!(network.packets.pop_front().unwrap().values, [12]);
+                assert!(network.packets.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn deadlock() {
+        both(
+            || Network::ring(&[3, 0, 3, 0, 4, 0, 99], &[1, 2]),
+            |mut network, threaded| {
+                assert_eq!(run(&mut network, threaded), Ok(Outcome::Deadlock));
+                assert!(network.nodes().iter().all(|node| node.machine.pc() == 2));
+            },
+        );
+    }
+
+    #[test]
+    fn failing_node() {
+        both(
+            || {
+                let mut network = Network::new();
+                network.add(Machine::new(&[104, 7, 99]), Route::Nodes(vec![1]));
+                network.add(Machine::new(&[3, 0, 42]), Route::Outside);
+                network
+            },
+            |mut network, threaded| {
+                assert_eq!(
+                    run(&mut network, threaded),
+                    Err(NetworkError::Failed {
+                        node: 1,
+                        error: MachineError::UnknownOpCode { pc: 2, value: 42 },
+                    })
+                );
+            },
+        );
+
+        // A node spinning without I/O is stopped rather than waited for.
+        let mut network = Network::new();
+        network.add(Machine::new(&[1105, 1, 0]), Route::Outside);
+        network.add(Machine::new(&[42]), Route::Outside);
+        assert_eq!(
+            network.run_threaded(),
+            Err(NetworkError::Failed {
+                node: 1,
+                error: MachineError::UnknownOpCode { pc: 0, value: 42 },
+            })
+        );
+    }
+
+    #[test]
+    fn bad_routes() {
+        for threaded in &[false, true] {
+            let mut network = Network::new();
+            network.add(Machine::new(&[99]), Route::Nodes(vec![0, 2]));
+            network.add(Machine::new(&[99]), Route::Outside);
+            assert_eq!(
+                run(&mut network, *threaded),
+                Err(NetworkError::BadRoute {
+                    node: 0,
+                    route: Route::Nodes(vec![0, 2]),
+                })
+            );
+
+            let mut network = Network::new();
+            network.add(Machine::new(&[99]), Route::Packets { width: 0 });
+            assert_eq!(
+                run(&mut network, *threaded),
+                Err(NetworkError::BadRoute {
+                    node: 0,
+                    route: Route::Packets { width: 0 },
+                })
+            );
+            assert_eq!(network.last_output(5), None);
+        }
+    }
+}
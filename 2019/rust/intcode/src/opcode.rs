@@ -0,0 +1,80 @@
+use num_derive::FromPrimitive;
+
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, Hash, Ord, PartialEq, PartialOrd)]
+pub enum OpCode {
+    Add = 1,
+    Multiply = 2,
+    Input = 3,
+    Output = 4,
+    JumpIfTrue = 5,
+    JumpIfNot = 6,
+    LessThan = 7,
+    Equals = 8,
+    AdjustRelativeBase = 9,
+    End = 99,
+}
+
+impl OpCode {
+    /// Number of parameters that follow the opcode in memory.
+    pub fn parameters(self) -> usize {
+        match self {
+            OpCode::Add | OpCode::Multiply | OpCode::LessThan | OpCode::Equals => 3,
+            OpCode::JumpIfTrue | OpCode::JumpIfNot => 2,
+            OpCode::Input | OpCode::Output | OpCode::AdjustRelativeBase => 1,
+            OpCode::End => 0,
+        }
+    }
+
+    /// Number of memory cells the whole instruction occupies, opcode included.
+    pub fn size(self) -> usize {
+        self.parameters() + 1
+    }
+
+    /// Position of the parameter the instruction writes through, if it writes to memory.
+    pub fn target(self) -> Option<usize> {
+        match self {
+            OpCode::Add | OpCode::Multiply | OpCode::LessThan | OpCode::Equals => Some(3),
+            OpCode::Input => Some(1),
+            _ => None,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            OpCode::Add => "add",
+            OpCode::Multiply => "mul",
+            OpCode::Input => "in",
+            OpCode::Output => "out",
+            OpCode::JumpIfTrue => "jt",
+            OpCode::JumpIfNot => "jf",
+            OpCode::LessThan => "lt",
+            OpCode::Equals => "eq",
+            OpCode::AdjustRelativeBase => "arb",
+            OpCode::End => "hlt",
+        }
+    }
+
+    /// Looks up an opcode by its mnemonic, ignoring case.
+    pub fn from_mnemonic(mnemonic: &str) -> Option<OpCode> {
+        match mnemonic.to_lowercase().as_str() {
+            "add" => Some(OpCode::Add),
+            "mul" => Some(OpCode::Multiply),
+            "in" => Some(OpCode::Input),
+            "out" => Some(OpCode::Output),
+            "jt" => Some(OpCode::JumpIfTrue),
+            "jf" => Some(OpCode::JumpIfNot),
+            "lt" => Some(OpCode::LessThan),
+            "eq" => Some(OpCode::Equals),
+            "arb" => Some(OpCode::AdjustRelativeBase),
+            "hlt" => Some(OpCode::End),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
+pub enum ParameterMode {
+    Position = 0,
+    Immediate = 1,
+    Relative = 2,
+}
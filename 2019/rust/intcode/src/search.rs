@@ -0,0 +1,333 @@
+//! Searching a program's parameters for settings that give a wanted result.
+//!
+//! A [`Search`] patches some memory cells or input slots with every combination of values from
+//! their ranges, runs each combination on its own machine in parallel and tests the result with
+//! a predicate. [`Search::first`] stops at the first match in the order a loop nested in the
+//! order of the patches would find it, and [`Search::all`] collects every match.
+//!
+//! Day 2 patches cells 1 and 2 and looks at memory cell 0 after the program halts, while a
+//! program that talks through I/O can have its inputs patched and its outputs tested the same
+//! way.
+
+use crate::{Machine, MachineError, QueueTerminal, State};
+use rayon::prelude::*;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How many times per search progress is reported.
+const REPORTS: u64 = 100;
+
+/// Where a searched value goes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Patch {
+    /// The memory cell at this address, before the program starts.
+    Memory(usize),
+    /// The input at this position in the queue given to the program.
+    Input(usize),
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Patch::Memory(address) => write!(f, "[{}]", address),
+            Patch::Input(slot) => write!(f, "in{}", slot),
+        }
+    }
+}
+
+/// Everything one run left behind, for the predicate to look at.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Run {
+    /// The value given to each patch, in the order they were added.
+    pub values: Vec<i64>,
+    pub result: Result<State, MachineError>,
+    pub outputs: Vec<i64>,
+    pub memory: Vec<i64>,
+}
+
+/// How far a search has got, as passed to the callback given to [`Search::progress`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Progress {
+    pub tried: u64,
+    pub total: u64,
+    pub matches: u64,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} ({}%), {} match{}",
+            self.tried,
+            self.total,
+            self.tried * 100 / self.total.max(1),
+            self.matches,
+            if self.matches == 1 { "" } else { "es" }
+        )
+    }
+}
+
+pub struct Search<'a> {
+    program: &'a [i64],
+    inputs: Vec<i64>,
+    patches: Vec<(Patch, RangeInclusive<i64>)>,
+    setup: Box<dyn Fn(&mut Machine) + Send + Sync + 'a>,
+    progress: Option<Box<dyn Fn(Progress) + Send + Sync + 'a>>,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(program: &'a [i64]) -> Search<'a> {
+        Search {
+            program,
+            inputs: Vec::new(),
+            patches: Vec::new(),
+            setup: Box::new(|_| {}),
+            progress: None,
+        }
+    }
+
+    /// Tries every value in `range` at `patch`. Earlier patches vary slowest.
+    pub fn patch(mut self, patch: Patch, range: RangeInclusive<i64>) -> Search<'a> {
+        self.patches.push((patch, range));
+        self
+    }
+
+    /// The inputs every run starts with, before [`Patch::Input`] values are put in. Slots past
+    /// the end are filled with zeros.
+    pub fn inputs(mut self, inputs: &[i64]) -> Search<'a> {
+        self.inputs = inputs.to_vec();
+        self
+    }
+
+    /// Calls `setup` on every machine before it runs, for instance to put a step limit on it
+    /// so one bad setting cannot hang the whole search.
+    pub fn configure(mut self, setup: impl Fn(&mut Machine) + Send + Sync + 'a) -> Search<'a> {
+        self.setup = Box::new(setup);
+        self
+    }
+
+    /// Calls `report` from the worker threads about a hundred times as the search goes.
+    pub fn progress(mut self, report: impl Fn(Progress) + Send + Sync + 'a) -> Search<'a> {
+        self.progress = Some(Box::new(report));
+        self
+    }
+
+    /// How many combinations there are to try.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `u64::MAX`.
+    pub fn total(&self) -> u64 {
+        self.patches
+            .iter()
+            .map(|(_, range)| size(range))
+            .try_fold(1u64, u64::checked_mul)
+            .expect("Too many combinations to search")
+    }
+
+    /// The values of the `index`th combination.
+    fn values(&self, mut index: u64) -> Vec<i64> {
+        let mut values = vec![0; self.patches.len()];
+
+        for (value, (_, range)) in values.iter_mut().zip(&self.patches).rev() {
+            let size = size(range);
+            *value = (*range.start() as i128 + (index % size) as i128) as i64;
+            index /= size;
+        }
+
+        values
+    }
+
+    /// Runs the program with `values` at the patches.
+    pub fn run(&self, values: &[i64]) -> Run {
+        let mut machine = Machine::new(self.program);
+        let mut inputs = self.inputs.clone();
+        let mut patched = Ok(());
+
+        for ((patch, _), &value) in self.patches.iter().zip(values) {
+            match *patch {
+                Patch::Memory(address) => patched = patched.and(machine.write(address, value)),
+                Patch::Input(slot) => {
+                    if slot >= inputs.len() {
+                        inputs.resize(slot + 1, 0);
+                    }
+
+                    inputs[slot] = value;
+                }
+            }
+        }
+
+        (self.setup)(&mut machine);
+
+        let mut terminal = QueueTerminal::new(&inputs);
+        let result = patched.and_then(|_| machine.resume(&mut terminal));
+
+        Run {
+            values: values.to_vec(),
+            result,
+            outputs: terminal.outputs.into_iter().collect(),
+            memory: machine.memory().to_vec(),
+        }
+    }
+
+    /// Runs the `index`th combination, keeping it if it matches and counting it towards
+    /// progress either way.
+    fn attempt(
+        &self,
+        index: u64,
+        total: u64,
+        tried: &AtomicU64,
+        matches: &AtomicU64,
+        predicate: &(impl Fn(&Run) -> bool + Sync),
+    ) -> Option<Run> {
+        let run = self.run(&self.values(index));
+        let matched = predicate(&run);
+
+        if matched {
+            matches.fetch_add(1, Ordering::SeqCst);
+        }
+
+        // Counting matches before tries means the last report has seen every match.
+        let count = tried.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if let Some(report) = &self.progress {
+            if count.is_multiple_of(total.div_ceil(REPORTS)) || count == total {
+                report(Progress {
+                    tried: count,
+                    total,
+                    matches: matches.load(Ordering::SeqCst),
+                });
+            }
+        }
+
+        Some(run).filter(|_| matched)
+    }
+
+    /// The first combination, in order, whose run satisfies `predicate`. Combinations after
+    /// one that matches are abandoned as soon as possible.
+    pub fn first(&self, predicate: impl Fn(&Run) -> bool + Sync) -> Option<Run> {
+        let total = self.total();
+        let (tried, matches) = (AtomicU64::new(0), AtomicU64::new(0));
+
+        (0..total)
+            .into_par_iter()
+            .find_map_first(|index| self.attempt(index, total, &tried, &matches, &predicate))
+    }
+
+    /// Every combination whose run satisfies `predicate`, in order.
+    pub fn all(&self, predicate: impl Fn(&Run) -> bool + Sync) -> Vec<Run> {
+        let total = self.total();
+        let (tried, matches) = (AtomicU64::new(0), AtomicU64::new(0));
+
+        (0..total)
+            .into_par_iter()
+            .filter_map(|index| self.attempt(index, total, &tried, &matches, &predicate))
+            .collect()
+    }
+}
+
+fn size(range: &RangeInclusive<i64>) -> u64 {
+    (*range.end() as i128 - *range.start() as i128 + 1).max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Patch, Progress, Search};
+    use crate::examples::NOUN_VERB;
+    use crate::{MachineError, State};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+
+    /// Outputs the product of its two inputs.
+    const PRODUCT: [i64; 11] = [3, 11, 3, 12, 2, 11, 12, 13, 4, 13, 99];
+
+    #[test]
+    fn memory_patches() {
+        let search = Search::new(NOUN_VERB)
+            .patch(Patch::Memory(1), 0..=99)
+            .patch(Patch::Memory(2), 0..=20);
+        assert_eq!(search.total(), 2100);
+
+        let run = search.first(|run| run.memory[0] == 89).unwrap();
+        assert_eq!(run.values, vec![10, 19]);
+        assert_eq!(run.result, Ok(State::Halted));
+
+        let all: Vec<_> = search
+            .all(|run| run.memory[0] == 89)
+            .into_iter()
+            .map(|run| run.values)
+            .collect();
+        assert_eq!(all, vec![vec![10, 19], vec![11, 12], vec![12, 5]]);
+
+        assert_eq!(search.first(|run| run.memory[0] == -1), None);
+    }
+
+    #[test]
+    fn input_patches() {
+        let search = Search::new(&PRODUCT)
+            .inputs(&[1])
+            .patch(Patch::Input(1), -5..=5)
+            .patch(Patch::Input(0), -5..=5);
+        let twelves: Vec<_> = search
+            .all(|run| run.outputs == [12])
+            .into_iter()
+            .map(|run| run.values)
+            .collect();
+        assert_eq!(
+            twelves,
+            vec![vec![-4, -3], vec![-3, -4], vec![3, 4], vec![4, 3]]
+        );
+
+        // Without the halt the program runs off into zeros and fails.
+        let failing = Search::new(&PRODUCT[..10]).inputs(&[2, 3]);
+        assert_eq!(
+            failing.run(&[]).result,
+            Err(MachineError::UnknownOpCode { pc: 10, value: 0 })
+        );
+    }
+
+    #[test]
+    fn configure() {
+        let configured = AtomicU64::new(0);
+        let limit = 3;
+        let search = Search::new(NOUN_VERB)
+            .patch(Patch::Memory(1), 0..=9)
+            .configure(|machine| {
+                configured.fetch_add(1, Ordering::Relaxed);
+                machine.set_step_limit(Some(limit));
+            });
+
+        assert!(search.all(|run| run.result.is_ok()).is_empty());
+        drop(search);
+        assert_eq!(configured.into_inner(), 10);
+    }
+
+    #[test]
+    fn progress() {
+        let reports = Mutex::new(Vec::new());
+        let search = Search::new(NOUN_VERB)
+            .patch(Patch::Memory(1), 0..=9)
+            .patch(Patch::Memory(2), 0..=99)
+            .configure(|machine| machine.set_step_limit(Some(100)))
+            .progress(|progress| reports.lock().unwrap().push(progress));
+
+        assert_eq!(search.all(|run| run.memory[0] < 10).len(), 13);
+        drop(search);
+
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort_by_key(|progress| progress.tried);
+        assert_eq!(reports.len(), 100);
+
+        let last = reports[99];
+        assert_eq!(
+            last,
+            Progress {
+                tried: 1000,
+                total: 1000,
+                matches: 13
+            }
+        );
+        assert_eq!(last.to_string(), "1000/1000 (100%), 13 matches");
+    }
+}
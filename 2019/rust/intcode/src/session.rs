@@ -0,0 +1,320 @@
+//! Recording a machine's input and output, and replaying the recording as a test fixture.
+//!
+//! A [`Recorder`] wraps the terminal of a live run and keeps every value passed through it as a
+//! [`Session`]. Sessions are saved as text, one event per line:
+//!
+//! ```text
+//! # lines starting with # are ignored
+//! in 5
+//! out 3188550
+//! ```
+//!
+//! [`replay`] runs a machine against a session, feeding it the recorded inputs in order, and
+//! reports where the values it writes first stop matching the recording.
+
+use crate::error::MachineError;
+use crate::machine::Machine;
+use crate::terminal::Terminal;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Events shown before the first difference in a divergence report.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    Input(i64),
+    Output(i64),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Input(value) => write!(f, "in {}", value),
+            Event::Output(value) => write!(f, "out {}", value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Session {
+    pub events: Vec<Event>,
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionError {
+    /// 1-based line of the session file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for SessionError {}
+
+impl FromStr for Session {
+    type Err = SessionError;
+
+    fn from_str(text: &str) -> Result<Session, SessionError> {
+        let mut events = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| SessionError {
+                line: i + 1,
+                message,
+            };
+            let (kind, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| error(format!("expected `in` or `out` and a value: `{}`", line)))?;
+            let value: i64 = value
+                .trim()
+                .parse()
+                .map_err(|_| error(format!("`{}` is not an integer", value.trim())))?;
+
+            events.push(match kind {
+                "in" => Event::Input(value),
+                "out" => Event::Output(value),
+                _ => return Err(error(format!("unknown event `{}`", kind))),
+            });
+        }
+
+        Ok(Session { events })
+    }
+}
+
+/// Passes everything through to `terminal`, keeping a copy in `session`.
+pub struct Recorder<T> {
+    pub terminal: T,
+    pub session: Session,
+}
+
+impl<T: Terminal> Recorder<T> {
+    pub fn new(terminal: T) -> Recorder<T> {
+        Recorder {
+            terminal,
+            session: Session::default(),
+        }
+    }
+}
+
+impl<T: Terminal> Terminal for Recorder<T> {
+    fn input(&mut self) -> Option<i64> {
+        let value = self.terminal.input()?;
+        self.session.events.push(Event::Input(value));
+        Some(value)
+    }
+
+    fn output(&mut self, val: i64) {
+        self.session.events.push(Event::Output(val));
+        self.terminal.output(val);
+    }
+}
+
+/// Feeds a machine the inputs of a session and records what actually happens.
+pub struct Replay<'a> {
+    session: &'a Session,
+    /// Index in the session of the next event to look for an input from.
+    next: usize,
+    pub events: Vec<Event>,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(session: &'a Session) -> Replay<'a> {
+        Replay {
+            session,
+            next: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Where the replayed events first differ from the session, if anywhere.
+    pub fn divergence(&self) -> Option<Divergence> {
+        let expected = &self.session.events;
+        let index = (0..expected.len().max(self.events.len()))
+            .find(|&i| expected.get(i) != self.events.get(i))?;
+
+        Some(Divergence {
+            index,
+            context: expected[index.saturating_sub(CONTEXT)..index].to_vec(),
+            expected: expected.get(index).cloned(),
+            actual: self.events.get(index).cloned(),
+        })
+    }
+}
+
+impl Terminal for Replay<'_> {
+    fn input(&mut self) -> Option<i64> {
+        let events = &self.session.events[self.next..];
+        let (offset, value) = events
+            .iter()
+            .enumerate()
+            .find_map(|(i, event)| match event {
+                Event::Input(value) => Some((i, *value)),
+                Event::Output(_) => None,
+            })?;
+
+        self.next += offset + 1;
+        self.events.push(Event::Input(value));
+        Some(value)
+    }
+
+    fn output(&mut self, val: i64) {
+        self.events.push(Event::Output(val));
+    }
+}
+
+/// The first event where a replay differs from its session.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// Index of the first differing event.
+    pub index: usize,
+    /// The matching events just before it.
+    pub context: Vec<Event>,
+    /// `None` where one side ended before the other.
+    pub expected: Option<Event>,
+    pub actual: Option<Event>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "replay diverges at event {}:", self.index)?;
+
+        let start = self.index - self.context.len();
+
+        for (i, event) in self.context.iter().enumerate() {
+            writeln!(f, "  {:>6}  {}", start + i, event)?;
+        }
+
+        match &self.expected {
+            Some(event) => writeln!(f, "- {:>6}  {}", self.index, event)?,
+            None => writeln!(f, "- {:>6}  (end of session)", self.index)?,
+        }
+
+        match &self.actual {
+            Some(event) => write!(f, "+ {:>6}  {}", self.index, event),
+            None => write!(f, "+ {:>6}  (end of replay)", self.index),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReplayError {
+    Diverged(Divergence),
+    Machine(MachineError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Diverged(divergence) => write!(f, "{}", divergence),
+            ReplayError::Machine(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// Runs `machine` on the inputs recorded in `session` until it halts or runs out of them, and
+/// checks that it reads and writes exactly what was recorded.
+pub fn replay(machine: &mut Machine, session: &Session) -> Result<(), ReplayError> {
+    let mut replay = Replay::new(session);
+    let result = machine.resume(&mut replay);
+
+    if let Some(divergence) = replay.divergence() {
+        return Err(ReplayError::Diverged(divergence));
+    }
+
+    result.map(|_| ()).map_err(ReplayError::Machine)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replay, Divergence, Event, Recorder, ReplayError, Session};
+    use crate::machine::Machine;
+    use crate::terminal::QueueTerminal;
+
+    // Outputs each input doubled until it reads a zero.
+    const DOUBLER: [i64; 16] = [
+        3, 15, 1006, 15, 14, 1002, 15, 2, 15, 4, 15, 1105, 1, 0, 99, 0,
+    ];
+
+    #[test]
+    fn record_and_replay() {
+        let program = DOUBLER;
+        let mut recorder = Recorder::new(QueueTerminal::new(&[4, 7, 0]));
+        Machine::new(&program).execute(&mut recorder).unwrap();
+
+        let text = recorder.session.to_string();
+        assert_eq!(text, "in 4\nout 8\nin 7\nout 14\nin 0\n");
+        assert_eq!(recorder.terminal.outputs, [8, 14]);
+
+        let session: Session = format!("# doubler\n\n{}", text).parse().unwrap();
+        assert_eq!(session, recorder.session);
+        assert_eq!(replay(&mut Machine::new(&program), &session), Ok(()));
+    }
+
+    #[test]
+    fn divergence() {
+        let program = DOUBLER;
+        let session: Session = "in 4\nout 8\nin 7\nout 14\nin 0\n".parse().unwrap();
+
+        // Tripling instead of doubling.
+        let mut changed = program;
+        changed[7] = 3;
+
+        let error = replay(&mut Machine::new(&changed), &session).unwrap_err();
+        let divergence = Divergence {
+            index: 1,
+            context: vec![Event::Input(4)],
+            expected: Some(Event::Output(8)),
+            actual: Some(Event::Output(12)),
+        };
+        assert_eq!(error, ReplayError::Diverged(divergence));
+        assert_eq!(
+            error.to_string(),
+            "replay diverges at event 1:\n       0  in 4\n-      1  out 8\n+      1  out 12"
+        );
+
+        // Halting after the first value leaves the rest of the session unreplayed.
+        let mut early = program;
+        early[11] = 99;
+
+        match replay(&mut Machine::new(&early), &session) {
+            Err(ReplayError::Diverged(divergence)) => {
+                assert_eq!(divergence.index, 2);
+                assert_eq!(divergence.actual, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let error = "in 1\nout x\n".parse::<Session>().unwrap_err();
+        assert_eq!(error.to_string(), "line 2: `x` is not an integer");
+
+        let error = "inp 1\n".parse::<Session>().unwrap_err();
+        assert_eq!(error.to_string(), "line 1: unknown event `inp`");
+    }
+}
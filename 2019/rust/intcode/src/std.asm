@@ -0,0 +1,119 @@
+; Intcode standard library, included with `.include "std"`.
+;
+; Functions follow the calling convention of `call`: arguments are pushed in order, the last
+; one is at [r-2] inside the function and its result, if any, replaces that last argument.
+; The caller pops the result and drops any other arguments. Functions use the stack above
+; the relative base as scratch space.
+;
+; Include this after the program's code, since it starts with code of its own.
+
+.macro std_load from, to
+; Copies the cell whose address is in `from` to `to`.
+        add %from, #0 -> [std_load%@+1]
+std_load%@:
+        add [0], #0 -> %to
+.endm
+
+.macro std_store from, to
+; Copies `from` to the cell whose address is in `to`.
+        add %to, #0 -> [std_store%@+3]
+std_store%@:
+        add %from, #0 -> [0]
+.endm
+
+; print_number(n): writes n in decimal, with a minus sign if it is negative. Leaves n.
+print_number:
+        add [r-2], #0 -> [r0]           ; what is left to print, never above zero since
+        lt [r0], #0 -> [r1]             ; the most negative number cannot be negated
+        jf [r1], #print_number_negate
+        out #45
+        jt #1, #print_number_digits
+print_number_negate:
+        mul [r0], #-1 -> [r0]
+print_number_digits:
+        add #0, #0 -> [r1]              ; nonzero once a digit has been printed
+        add #print_number_powers, #0 -> [r2]
+print_number_power:
+        std_load [r2], [r3]             ; the power of ten for this digit
+        add #0, #0 -> [r4]              ; the digit
+print_number_add:
+        mul [r3], #-1 -> [r5]
+        lt [r5], [r0] -> [r5]
+        jt [r5], #print_number_digit
+        add [r0], [r3] -> [r0]
+        add [r4], #1 -> [r4]
+        jt #1, #print_number_add
+print_number_digit:
+        add [r1], [r4] -> [r1]
+        eq [r3], #1 -> [r5]             ; the units are printed even when zero
+        add [r1], [r5] -> [r5]
+        jf [r5], #print_number_next
+        add [r4], #48 -> [r4]
+        out [r4]
+print_number_next:
+        eq [r3], #1 -> [r5]
+        jt [r5], #print_number_done
+        add [r2], #1 -> [r2]
+        jt #1, #print_number_power
+print_number_done:
+        ret
+print_number_powers:
+        .data 1000000000000000000, 100000000000000000, 10000000000000000
+        .data 1000000000000000, 100000000000000, 10000000000000, 1000000000000
+        .data 100000000000, 10000000000, 1000000000, 100000000, 10000000, 1000000
+        .data 100000, 10000, 1000, 100, 10, 1
+
+; print_string(address): writes the zero-terminated string at address. Leaves address.
+print_string:
+        add [r-2], #0 -> [r0]           ; the next character's address
+print_string_next:
+        std_load [r0], [r1]
+        jf [r1], #print_string_done
+        out [r1]
+        add [r0], #1 -> [r0]
+        jt #1, #print_string_next
+print_string_done:
+        ret
+
+; read_line(buffer): reads input up to a newline into buffer as a zero-terminated string,
+; without the newline. Returns the length of the line.
+read_line:
+        add [r-2], #0 -> [r0]           ; where the next character goes
+read_line_next:
+        in -> [r1]
+        eq [r1], #10 -> [r2]
+        jt [r2], #read_line_done
+        std_store [r1], [r0]
+        add [r0], #1 -> [r0]
+        jt #1, #read_line_next
+read_line_done:
+        std_store #0, [r0]
+        mul [r-2], #-1 -> [r1]
+        add [r0], [r1] -> [r-2]
+        ret
+
+; power(base, exponent): returns base raised to a non-negative exponent.
+power:
+        add #1, #0 -> [r0]              ; the result so far
+power_next:
+        lt #0, [r-2] -> [r1]
+        jf [r1], #power_done
+        mul [r0], [r-3] -> [r0]
+        add [r-2], #-1 -> [r-2]
+        jt #1, #power_next
+power_done:
+        add [r0], #0 -> [r-2]
+        ret
+
+; factorial(n): returns n!, or 1 if n is not positive.
+factorial:
+        add #1, #0 -> [r0]              ; the result so far
+factorial_next:
+        lt #1, [r-2] -> [r1]
+        jf [r1], #factorial_done
+        mul [r0], [r-2] -> [r0]
+        add [r-2], #-1 -> [r-2]
+        jt #1, #factorial_next
+factorial_done:
+        add [r0], #0 -> [r-2]
+        ret
@@ -0,0 +1,1057 @@
+//! Symbolic execution of Intcode programs, for solving which inputs give a wanted result.
+//!
+//! An [`Executor`] runs a program over memory cells holding [`Expr`]s instead of numbers. Cells
+//! or inputs set to a symbol carry it through `Add`, `Multiply` and the comparisons, so after
+//! the run a cell or output is an expression in the symbols. A jump whose condition depends on
+//! a symbol forks the run, and each resulting [`Path`] keeps the conditions that led down it.
+//!
+//! [`solve`] then looks for symbol values within given ranges that make an expression equal a
+//! target on a path. Linear expressions are solved for their last symbol directly, so day 2's
+//! `noun` and `verb` take a hundred candidates instead of ten thousand runs. With the `smt`
+//! feature, `smt::solve` hands the same problem to an external SMT solver instead.
+//!
+//! Reads through a symbolic address become a [`Expr::Load`] of memory as it was at the time.
+//! Writes, jumps and relative base changes through symbolic values are not supported and end
+//! the path with an error.
+
+use crate::error::MachineError;
+use crate::machine::{State, MEMORY_LIMIT};
+use crate::opcode::{OpCode, ParameterMode};
+use num_traits::FromPrimitive;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/// Most paths one run may fork into.
+const MAX_PATHS: usize = 256;
+
+/// Most instructions a single path may execute.
+const MAX_STEPS: u64 = 1_000_000;
+
+/// Most candidate assignments [`solve`] tries before giving up.
+const MAX_CANDIDATES: u64 = 10_000_000;
+
+/// A memory cell, input or output in terms of the symbols.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Const(i64),
+    Symbol(Rc<str>),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    /// 1 if the left side is less than the right, 0 otherwise.
+    LessThan(Rc<Expr>, Rc<Expr>),
+    /// 1 if both sides are equal, 0 otherwise.
+    Equals(Rc<Expr>, Rc<Expr>),
+    /// The cell at a symbolic address in memory as it was when read.
+    Load(Rc<Expr>, Rc<Vec<Expr>>),
+}
+
+impl Expr {
+    pub fn symbol(name: &str) -> Expr {
+        Expr::Symbol(name.into())
+    }
+
+    /// The value of this expression, if it does not depend on any symbol.
+    pub fn constant(&self) -> Option<i64> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// `a + b`, folding constants. `None` if constants overflow.
+    fn add(a: Expr, b: Expr) -> Option<Expr> {
+        Some(match (a.constant(), b.constant()) {
+            (Some(a), Some(b)) => Expr::Const(a.checked_add(b)?),
+            (Some(0), _) => b,
+            (_, Some(0)) => a,
+            _ => Expr::Add(Rc::new(a), Rc::new(b)),
+        })
+    }
+
+    /// `a * b`, folding constants. `None` if constants overflow.
+    fn mul(a: Expr, b: Expr) -> Option<Expr> {
+        Some(match (a.constant(), b.constant()) {
+            (Some(a), Some(b)) => Expr::Const(a.checked_mul(b)?),
+            (Some(0), _) | (_, Some(0)) => Expr::Const(0),
+            (Some(1), _) => b,
+            (_, Some(1)) => a,
+            _ => Expr::Mul(Rc::new(a), Rc::new(b)),
+        })
+    }
+
+    fn less_than(a: Expr, b: Expr) -> Expr {
+        match (a.constant(), b.constant()) {
+            (Some(a), Some(b)) => Expr::Const((a < b) as i64),
+            _ => Expr::LessThan(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    fn equals(a: Expr, b: Expr) -> Expr {
+        match (a.constant(), b.constant()) {
+            (Some(a), Some(b)) => Expr::Const((a == b) as i64),
+            _ if a == b => Expr::Const(1),
+            _ => Expr::Equals(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    /// The value of this expression with symbols replaced from `values`, or `None` if one is
+    /// missing, arithmetic overflows or a load goes through a negative address, any of which
+    /// the machine would not get past either.
+    pub fn eval(&self, values: &BTreeMap<String, i64>) -> Option<i64> {
+        Some(match self {
+            Expr::Const(value) => *value,
+            Expr::Symbol(name) => *values.get(&**name)?,
+            Expr::Add(a, b) => a.eval(values)?.checked_add(b.eval(values)?)?,
+            Expr::Mul(a, b) => a.eval(values)?.checked_mul(b.eval(values)?)?,
+            Expr::LessThan(a, b) => (a.eval(values)? < b.eval(values)?) as i64,
+            Expr::Equals(a, b) => (a.eval(values)? == b.eval(values)?) as i64,
+            Expr::Load(address, memory) => {
+                let address = address.eval(values)?;
+
+                if address < 0 {
+                    return None;
+                }
+
+                match memory.get(address as usize) {
+                    Some(cell) => cell.eval(values)?,
+                    None => 0,
+                }
+            }
+        })
+    }
+
+    /// Names of the symbols this expression depends on.
+    pub fn symbols(&self) -> BTreeSet<Rc<str>> {
+        let mut symbols = BTreeSet::new();
+        self.collect_symbols(&mut symbols);
+        symbols
+    }
+
+    fn collect_symbols(&self, symbols: &mut BTreeSet<Rc<str>>) {
+        match self {
+            Expr::Const(_) => {}
+            Expr::Symbol(name) => {
+                symbols.insert(Rc::clone(name));
+            }
+            Expr::Add(a, b) | Expr::Mul(a, b) | Expr::LessThan(a, b) | Expr::Equals(a, b) => {
+                a.collect_symbols(symbols);
+                b.collect_symbols(symbols);
+            }
+            Expr::Load(address, memory) => {
+                address.collect_symbols(symbols);
+
+                for cell in memory.iter() {
+                    cell.collect_symbols(symbols);
+                }
+            }
+        }
+    }
+
+    /// This expression as a constant plus a multiple of each symbol, if it is one.
+    pub fn linear(&self) -> Option<Linear> {
+        match self {
+            Expr::Const(value) => Some(Linear {
+                constant: *value,
+                terms: BTreeMap::new(),
+            }),
+            Expr::Symbol(name) => Some(Linear {
+                constant: 0,
+                terms: vec![(Rc::clone(name), 1)].into_iter().collect(),
+            }),
+            Expr::Add(a, b) => {
+                let (mut a, b) = (a.linear()?, b.linear()?);
+                a.constant = a.constant.checked_add(b.constant)?;
+
+                for (name, coefficient) in b.terms {
+                    let sum = a.terms.get(&name).cloned().unwrap_or(0);
+                    a.terms.insert(name, sum.checked_add(coefficient)?);
+                }
+
+                a.terms.retain(|_, coefficient| *coefficient != 0);
+                Some(a)
+            }
+            Expr::Mul(a, b) => {
+                let (a, b) = (a.linear()?, b.linear()?);
+                let (scale, mut other) = match (a.terms.is_empty(), b.terms.is_empty()) {
+                    (true, _) => (a.constant, b),
+                    (_, true) => (b.constant, a),
+                    _ => return None,
+                };
+
+                other.constant = other.constant.checked_mul(scale)?;
+
+                for coefficient in other.terms.values_mut() {
+                    *coefficient = coefficient.checked_mul(scale)?;
+                }
+
+                other.terms.retain(|_, coefficient| *coefficient != 0);
+                Some(other)
+            }
+            _ => None,
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::LessThan(..) | Expr::Equals(..) => 0,
+            Expr::Add(..) => 1,
+            Expr::Mul(..) => 2,
+            Expr::Const(value) if *value < 0 => 2,
+            _ => 3,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter, parent: u8) -> fmt::Result {
+        if self.precedence() < parent {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (a, operator, b) = match self {
+            Expr::Const(value) => return write!(f, "{}", value),
+            Expr::Symbol(name) => return write!(f, "{}", name),
+            Expr::Load(address, _) => return write!(f, "mem[{}]", address),
+            Expr::Add(a, b) => (a, "+", b),
+            Expr::Mul(a, b) => (a, "*", b),
+            Expr::LessThan(a, b) => (a, "<", b),
+            Expr::Equals(a, b) => (a, "==", b),
+        };
+        let precedence = self.precedence();
+
+        a.fmt_operand(f, precedence)?;
+        write!(f, " {} ", operator)?;
+        b.fmt_operand(f, precedence + 1)
+    }
+}
+
+/// `constant + Σ coefficient * symbol`, with no zero coefficients.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Linear {
+    pub constant: i64,
+    pub terms: BTreeMap<Rc<str>, i64>,
+}
+
+impl fmt::Display for Linear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+
+        for (name, &coefficient) in &self.terms {
+            let sign = match (first, coefficient < 0) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            };
+
+            match coefficient.unsigned_abs() {
+                1 => write!(f, "{}{}", sign, name)?,
+                magnitude => write!(f, "{}{}*{}", sign, magnitude, name)?,
+            }
+
+            first = false;
+        }
+
+        match (first, self.constant) {
+            (true, constant) => write!(f, "{}", constant),
+            (false, 0) => Ok(()),
+            (false, constant) if constant < 0 => write!(f, " - {}", constant.unsigned_abs()),
+            (false, constant) => write!(f, " + {}", constant),
+        }
+    }
+}
+
+/// A branch taken on a symbolic condition: `condition` was nonzero if `holds`, zero otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraint {
+    pub condition: Expr,
+    pub holds: bool,
+}
+
+impl Constraint {
+    pub fn satisfied(&self, values: &BTreeMap<String, i64>) -> bool {
+        self.condition
+            .eval(values)
+            .is_some_and(|value| (value != 0) == self.holds)
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.holds {
+            true => write!(f, "{} != 0", self.condition),
+            false => write!(f, "{} == 0", self.condition),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SymbolicError {
+    /// The machine itself would fail, whatever the symbols are.
+    Machine(MachineError),
+    /// The instruction at `pc` depends on a symbol.
+    SymbolicInstruction { pc: usize },
+    /// The instruction at `pc` writes, jumps or moves the relative base by a symbolic amount.
+    SymbolicAddress { pc: usize },
+    /// The path ran for too long, probably in a loop whose exit depends on a symbol.
+    StepLimitExceeded { pc: usize },
+    /// The run forked into more paths than are explored.
+    TooManyPaths { pc: usize },
+}
+
+impl fmt::Display for SymbolicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolicError::Machine(error) => write!(f, "{}", error),
+            SymbolicError::SymbolicInstruction { pc } => {
+                write!(f, "instruction at pc {} depends on a symbol", pc)
+            }
+            SymbolicError::SymbolicAddress { pc } => {
+                write!(f, "symbolic address or jump target at pc {}", pc)
+            }
+            SymbolicError::StepLimitExceeded { pc } => {
+                write!(f, "path ran too long, stopped at pc {}", pc)
+            }
+            SymbolicError::TooManyPaths { pc } => write!(f, "too many paths, forking at pc {}", pc),
+        }
+    }
+}
+
+impl Error for SymbolicError {}
+
+impl From<MachineError> for SymbolicError {
+    fn from(error: MachineError) -> SymbolicError {
+        SymbolicError::Machine(error)
+    }
+}
+
+/// One way through the program, and where it ended up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    /// Conditions of the symbolic branches taken, in order.
+    pub constraints: Vec<Constraint>,
+    pub memory: Vec<Expr>,
+    pub outputs: Vec<Expr>,
+    /// How the path ended: halted, waiting for more input or failed.
+    pub end: Result<State, SymbolicError>,
+}
+
+impl Path {
+    /// Reads a cell; addresses never written to read as zero.
+    pub fn read(&self, address: usize) -> Expr {
+        self.memory.get(address).cloned().unwrap_or(Expr::Const(0))
+    }
+
+    /// Whether `values` send the program down this path.
+    pub fn admits(&self, values: &BTreeMap<String, i64>) -> bool {
+        self.constraints
+            .iter()
+            .all(|constraint| constraint.satisfied(values))
+    }
+}
+
+/// A path still being explored.
+#[derive(Clone)]
+struct Run {
+    path: Path,
+    pc: usize,
+    relative_base: i64,
+    steps: u64,
+    inputs: usize,
+}
+
+impl Run {
+    fn write(&mut self, address: usize, value: Expr) -> Result<(), SymbolicError> {
+        let memory = &mut self.path.memory;
+
+        if address >= memory.len() {
+            if address >= MEMORY_LIMIT {
+                Err(MachineError::MemoryLimitExceeded {
+                    pc: self.pc,
+                    address,
+                })?
+            }
+
+            memory.resize(address + 1, Expr::Const(0));
+        }
+
+        memory[address] = value;
+        Ok(())
+    }
+
+    fn concrete(&self, value: &Expr) -> Result<i64, SymbolicError> {
+        value
+            .constant()
+            .ok_or(SymbolicError::SymbolicAddress { pc: self.pc })
+    }
+
+    fn to_address(&self, value: i64) -> Result<usize, SymbolicError> {
+        if value < 0 {
+            Err(MachineError::NegativeAddress {
+                pc: self.pc,
+                address: value,
+            })?
+        }
+
+        Ok(value as usize)
+    }
+
+    /// The address a parameter refers to, as an expression.
+    fn address(&self, mode: ParameterMode, parameter: Expr) -> Result<Expr, SymbolicError> {
+        match mode {
+            ParameterMode::Position => Ok(parameter),
+            ParameterMode::Relative => Expr::add(Expr::Const(self.relative_base), parameter).ok_or(
+                SymbolicError::Machine(MachineError::ValueOutOfRange { pc: self.pc }),
+            ),
+            ParameterMode::Immediate => unreachable!(),
+        }
+    }
+
+    fn get(&mut self, mode: ParameterMode, parameter: Expr) -> Result<Expr, SymbolicError> {
+        if mode == ParameterMode::Immediate {
+            return Ok(parameter);
+        }
+
+        match self.address(mode, parameter)? {
+            Expr::Const(address) => Ok(self.path.read(self.to_address(address)?)),
+            address => {
+                // The machine would fail on a negative address, so this path assumes there is
+                // none.
+                self.path.constraints.push(Constraint {
+                    condition: Expr::less_than(address.clone(), Expr::Const(0)),
+                    holds: false,
+                });
+
+                Ok(Expr::Load(
+                    Rc::new(address),
+                    Rc::new(self.path.memory.clone()),
+                ))
+            }
+        }
+    }
+
+    fn set(
+        &mut self,
+        mode: ParameterMode,
+        parameter: Expr,
+        position: usize,
+        value: Expr,
+    ) -> Result<(), SymbolicError> {
+        if mode == ParameterMode::Immediate {
+            Err(MachineError::ImmediateWrite {
+                pc: self.pc,
+                position,
+            })?
+        }
+
+        let address = self.address(mode, parameter)?;
+        let address = self.to_address(self.concrete(&address)?)?;
+        self.write(address, value)
+    }
+}
+
+/// Runs a program with some of its memory and input replaced by symbols.
+pub struct Executor {
+    memory: Vec<Expr>,
+    inputs: Vec<Expr>,
+}
+
+impl Executor {
+    pub fn new(program: &[i64]) -> Executor {
+        Executor {
+            memory: program.iter().map(|&value| Expr::Const(value)).collect(),
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Replaces the cell at `address` before the run starts.
+    pub fn set(&mut self, address: usize, value: Expr) {
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, Expr::Const(0));
+        }
+
+        self.memory[address] = value;
+    }
+
+    /// Queues a value for the program's input instructions.
+    pub fn input(&mut self, value: Expr) {
+        self.inputs.push(value);
+    }
+
+    /// Explores every path through the program, in the order their branches appear.
+    pub fn run(&self) -> Vec<Path> {
+        let mut pending = vec![Run {
+            path: Path {
+                constraints: Vec::new(),
+                memory: self.memory.clone(),
+                outputs: Vec::new(),
+                end: Ok(State::Running),
+            },
+            pc: 0,
+            relative_base: 0,
+            steps: 0,
+            inputs: 0,
+        }];
+        let mut paths = Vec::new();
+
+        while let Some(mut run) = pending.pop() {
+            let end = loop {
+                match self.step(&mut run) {
+                    Ok(None) => continue,
+                    Ok(Some(fork)) if paths.len() + pending.len() + 1 < MAX_PATHS => {
+                        // Explore the branch that falls through first, then the jump.
+                        pending.push(fork);
+                    }
+                    Ok(Some(fork)) => paths.push(Path {
+                        end: Err(SymbolicError::TooManyPaths { pc: run.pc }),
+                        ..fork.path
+                    }),
+                    Err(end) => break end,
+                }
+            };
+
+            run.path.end = end;
+            paths.push(run.path);
+        }
+
+        paths
+    }
+
+    /// Executes one instruction. Returns the other side of a symbolic branch, or the way the
+    /// path ended as an error, with `Ok` for states the machine stops in normally.
+    #[allow(clippy::type_complexity)]
+    fn step(&self, run: &mut Run) -> Result<Option<Run>, Result<State, SymbolicError>> {
+        let pc = run.pc;
+
+        if run.steps >= MAX_STEPS {
+            return Err(Err(SymbolicError::StepLimitExceeded { pc }));
+        }
+
+        let instruction = match run.path.read(pc) {
+            Expr::Const(value) => value,
+            _ => return Err(Err(SymbolicError::SymbolicInstruction { pc })),
+        };
+        let opcode: OpCode = FromPrimitive::from_i64(instruction % 100).ok_or(Err(
+            SymbolicError::Machine(MachineError::UnknownOpCode {
+                pc,
+                value: instruction,
+            }),
+        ))?;
+
+        if opcode == OpCode::End {
+            return Err(Ok(State::Halted));
+        }
+
+        let mut modes = Vec::with_capacity(3);
+        let mut mode_digits = instruction / 100;
+
+        for position in 1..=opcode.parameters() {
+            let mode = mode_digits % 10;
+            mode_digits /= 10;
+
+            modes.push(
+                FromPrimitive::from_i64(mode).ok_or(Err(SymbolicError::Machine(
+                    MachineError::InvalidParameterMode { pc, position, mode },
+                )))?,
+            );
+        }
+
+        let parameter = |position: usize| run.path.read(pc + position);
+        let (p1, p2, p3) = (parameter(1), parameter(2), parameter(3));
+        let mut fork = None;
+
+        let result: Result<(), SymbolicError> = (|| {
+            match opcode {
+                OpCode::Add | OpCode::Multiply | OpCode::LessThan | OpCode::Equals => {
+                    let a = run.get(modes[0], p1)?;
+                    let b = run.get(modes[1], p2)?;
+                    let value = match opcode {
+                        OpCode::Add => Expr::add(a, b),
+                        OpCode::Multiply => Expr::mul(a, b),
+                        OpCode::LessThan => Some(Expr::less_than(a, b)),
+                        _ => Some(Expr::equals(a, b)),
+                    }
+                    .ok_or(MachineError::ValueOutOfRange { pc })?;
+
+                    run.set(modes[2], p3, 3, value)?;
+                }
+                OpCode::Input => {
+                    let value = match self.inputs.get(run.inputs) {
+                        Some(value) => value.clone(),
+                        None => {
+                            return Err(SymbolicError::Machine(MachineError::InputExhausted { pc }))
+                        }
+                    };
+
+                    run.set(modes[0], p1, 1, value)?;
+                    run.inputs += 1;
+                }
+                OpCode::Output => {
+                    let value = run.get(modes[0], p1)?;
+                    run.path.outputs.push(value);
+                }
+                OpCode::JumpIfTrue | OpCode::JumpIfNot => {
+                    let condition = run.get(modes[0], p1)?;
+                    let target = run.get(modes[1], p2)?;
+                    let jumps = |nonzero: bool| nonzero == (opcode == OpCode::JumpIfTrue);
+
+                    match condition.constant() {
+                        Some(value) if jumps(value != 0) => {
+                            run.pc = run.to_address(run.concrete(&target)?)?;
+                            run.steps += 1;
+                            return Ok(());
+                        }
+                        Some(_) => {}
+                        None => {
+                            let mut taken = run.clone();
+                            taken.path.constraints.push(Constraint {
+                                condition: condition.clone(),
+                                holds: jumps(true),
+                            });
+                            taken.pc = taken.to_address(taken.concrete(&target)?)?;
+                            taken.steps += 1;
+                            fork = Some(taken);
+
+                            run.path.constraints.push(Constraint {
+                                condition,
+                                holds: !jumps(true),
+                            });
+                        }
+                    }
+                }
+                OpCode::AdjustRelativeBase => {
+                    let offset = run.get(modes[0], p1)?;
+                    run.relative_base = run
+                        .relative_base
+                        .checked_add(run.concrete(&offset)?)
+                        .ok_or(MachineError::ValueOutOfRange { pc })?;
+                }
+                OpCode::End => unreachable!(),
+            }
+
+            run.pc += opcode.size();
+            run.steps += 1;
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => Ok(fork),
+            Err(SymbolicError::Machine(MachineError::InputExhausted { .. })) => {
+                Err(Ok(State::NeedsInput))
+            }
+            Err(error) => Err(Err(error)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SolveError {
+    /// The expression or path depends on a symbol with no range to search.
+    Unbounded(String),
+    /// More candidates than are worth trying one by one.
+    TooManyCandidates,
+    /// The external solver could not be run or gave an answer that could not be read.
+    Solver(String),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unbounded(name) => write!(f, "no range given for `{}`", name),
+            SolveError::TooManyCandidates => write!(f, "too many candidates to search"),
+            SolveError::Solver(message) => write!(f, "solver: {}", message),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+/// Finds values for the symbols, each within its range in `ranges`, that send the program
+/// down `path` and make `expr` equal `target`.
+///
+/// Symbols are tried in the order of `ranges`, lowest first, so the answer is the first one a
+/// brute-force search nested the same way would find. When `expr` is linear the last symbol it
+/// depends on is solved for rather than searched.
+pub fn solve(
+    path: &Path,
+    expr: &Expr,
+    target: i64,
+    ranges: &[(&str, RangeInclusive<i64>)],
+) -> Result<Option<BTreeMap<String, i64>>, SolveError> {
+    let mut symbols = expr.symbols();
+
+    for constraint in &path.constraints {
+        symbols.extend(constraint.condition.symbols());
+    }
+
+    if let Some(name) = symbols
+        .iter()
+        .find(|&name| ranges.iter().all(|(range, _)| *range != &**name))
+    {
+        return Err(SolveError::Unbounded(name.to_string()));
+    }
+
+    let ranges: Vec<_> = ranges
+        .iter()
+        .filter(|(name, _)| symbols.contains(*name))
+        .collect();
+
+    // With a linear expression the last symbol in it follows from the others.
+    let linear = expr.linear();
+    let solved = linear.as_ref().and_then(|linear| {
+        ranges
+            .iter()
+            .rposition(|(name, _)| linear.terms.contains_key(*name))
+    });
+
+    let candidates = ranges
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| Some(i) != solved)
+        .try_fold(1u64, |count, (_, (_, range))| {
+            let size = (*range.end() as i128 - *range.start() as i128 + 1).max(0) as u64;
+            count.checked_mul(size)
+        })
+        .filter(|&count| count <= MAX_CANDIDATES)
+        .ok_or(SolveError::TooManyCandidates)?;
+
+    let mut values: BTreeMap<String, i64> = ranges
+        .iter()
+        .map(|(name, range)| (name.to_string(), *range.start()))
+        .collect();
+
+    for mut index in 0..candidates {
+        for (i, (name, range)) in ranges.iter().enumerate().rev() {
+            if Some(i) == solved {
+                continue;
+            }
+
+            let size = (*range.end() - *range.start() + 1) as u64;
+            values.insert(name.to_string(), *range.start() + (index % size) as i64);
+            index /= size;
+        }
+
+        if let (Some(linear), Some(i)) = (&linear, solved) {
+            let (name, range) = ranges[i];
+            let coefficient = linear.terms[*name] as i128;
+            let rest = linear
+                .terms
+                .iter()
+                .filter(|(other, _)| &***other != *name)
+                .map(|(other, &c)| c as i128 * values[&**other] as i128)
+                .sum::<i128>()
+                + linear.constant as i128;
+            let wanted = target as i128 - rest;
+
+            if wanted % coefficient != 0 {
+                continue;
+            }
+
+            let value = wanted / coefficient;
+
+            if value < *range.start() as i128 || value > *range.end() as i128 {
+                continue;
+            }
+
+            values.insert(name.to_string(), value as i64);
+        }
+
+        if expr.eval(&values) == Some(target) && path.admits(&values) {
+            return Ok(Some(values));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Solving through an external SMT solver that reads SMT-LIB 2 on stdin, such as `z3 -in`.
+///
+/// Symbols are unbounded integers to the solver, so it may find answers that overflow 64 bits
+/// on the way; every answer is checked on the path before being returned. Loads through
+/// symbolic addresses are not supported.
+#[cfg(feature = "smt")]
+pub mod smt {
+    use super::{Constraint, Expr, Path, SolveError};
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::ops::RangeInclusive;
+    use std::process::{Command, Stdio};
+
+    fn term(expr: &Expr) -> Result<String, SolveError> {
+        Ok(match expr {
+            Expr::Const(value) if *value < 0 => format!("(- {})", value.unsigned_abs()),
+            Expr::Const(value) => value.to_string(),
+            Expr::Symbol(name) => format!("|{}|", name),
+            Expr::Add(a, b) => format!("(+ {} {})", term(a)?, term(b)?),
+            Expr::Mul(a, b) => format!("(* {} {})", term(a)?, term(b)?),
+            Expr::LessThan(a, b) => format!("(ite (< {} {}) 1 0)", term(a)?, term(b)?),
+            Expr::Equals(a, b) => format!("(ite (= {} {}) 1 0)", term(a)?, term(b)?),
+            Expr::Load(..) => {
+                return Err(SolveError::Solver(
+                    "loads through symbolic addresses are not supported".to_string(),
+                ))
+            }
+        })
+    }
+
+    fn assertion(constraint: &Constraint) -> Result<String, SolveError> {
+        let condition = term(&constraint.condition)?;
+
+        Ok(match constraint.holds {
+            true => format!("(assert (not (= {} 0)))", condition),
+            false => format!("(assert (= {} 0))", condition),
+        })
+    }
+
+    /// The SMT-LIB 2 script asking for values that make `expr` equal `target` on `path`.
+    pub fn script(
+        path: &Path,
+        expr: &Expr,
+        target: i64,
+        ranges: &[(&str, RangeInclusive<i64>)],
+    ) -> Result<String, SolveError> {
+        let mut lines = vec!["(set-logic ALL)".to_string()];
+
+        for (name, range) in ranges {
+            lines.push(format!("(declare-const |{}| Int)", name));
+            lines.push(format!(
+                "(assert (<= {} |{}| {}))",
+                term(&Expr::Const(*range.start()))?,
+                name,
+                term(&Expr::Const(*range.end()))?
+            ));
+        }
+
+        for constraint in &path.constraints {
+            lines.push(assertion(constraint)?);
+        }
+
+        lines.push(format!(
+            "(assert (= {} {}))",
+            term(expr)?,
+            term(&Expr::Const(target))?
+        ));
+        lines.push("(check-sat)".to_string());
+
+        let names: Vec<String> = ranges
+            .iter()
+            .map(|(name, _)| format!("|{}|", name))
+            .collect();
+        lines.push(format!("(get-value ({}))", names.join(" ")));
+        lines.push(String::new());
+        Ok(lines.join("\n"))
+    }
+
+    /// Reads the solver's answer: `unsat`, or `sat` followed by `((|name| value) ...)`.
+    pub(super) fn parse(output: &str) -> Result<Option<BTreeMap<String, i64>>, SolveError> {
+        let bad = || SolveError::Solver(format!("unexpected output `{}`", output.trim()));
+        let mut lines = output.trim_start().splitn(2, '\n');
+
+        match lines.next().map(str::trim) {
+            Some("unsat") => return Ok(None),
+            Some("sat") => {}
+            _ => return Err(bad()),
+        }
+
+        let model = lines.next().ok_or_else(bad)?;
+        let tokens: Vec<String> = model
+            .replace('(', " ( ")
+            .replace(')', " ) ")
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        let mut values = BTreeMap::new();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            if tokens[i].starts_with('|') {
+                let name = tokens[i].trim_matches('|').to_string();
+                let (value, next) = match tokens.get(i + 1).map(String::as_str) {
+                    Some("(") if tokens.get(i + 2).map(String::as_str) == Some("-") => {
+                        (format!("-{}", tokens.get(i + 3).ok_or_else(bad)?), i + 4)
+                    }
+                    Some(value) => (value.to_string(), i + 2),
+                    None => return Err(bad()),
+                };
+
+                values.insert(name, value.parse().map_err(|_| bad())?);
+                i = next;
+            } else {
+                i += 1;
+            }
+        }
+
+        Ok(Some(values))
+    }
+
+    /// Like [`super::solve`], asking `solver` run with `args`, for example `z3` with `-in`.
+    pub fn solve(
+        solver: &str,
+        args: &[&str],
+        path: &Path,
+        expr: &Expr,
+        target: i64,
+        ranges: &[(&str, RangeInclusive<i64>)],
+    ) -> Result<Option<BTreeMap<String, i64>>, SolveError> {
+        let script = script(path, expr, target, ranges)?;
+        let failed = |e: std::io::Error| SolveError::Solver(format!("{}: {}", solver, e));
+        let mut child = Command::new(solver)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(failed)?;
+
+        child
+            .stdin
+            .take()
+            .expect("No stdin")
+            .write_all(script.as_bytes())
+            .map_err(failed)?;
+
+        let output = child.wait_with_output().map_err(failed)?;
+        let values = parse(&String::from_utf8_lossy(&output.stdout))?;
+
+        match values {
+            Some(values) if expr.eval(&values) == Some(target) && path.admits(&values) => {
+                Ok(Some(values))
+            }
+            Some(_) => Err(SolveError::Solver(
+                "answer does not hold with 64-bit arithmetic".to_string(),
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve, Executor, Expr, SolveError, SymbolicError};
+    use crate::error::MachineError;
+    use crate::examples::NOUN_VERB;
+    use crate::machine::{Machine, State};
+    use crate::terminal::QueueTerminal;
+
+    /// Outputs twice its input if the input is below 5, or the input plus 100 otherwise.
+    const BRANCH: [i64; 23] = [
+        3, 30, 1007, 30, 5, 31, 1006, 31, 16, 102, 2, 30, 32, 1105, 1, 20, 1001, 30, 100, 32, 4,
+        32, 99,
+    ];
+
+    #[test]
+    fn noun_verb() {
+        let mut executor = Executor::new(NOUN_VERB);
+        executor.set(1, Expr::symbol("noun"));
+        executor.set(2, Expr::symbol("verb"));
+
+        let paths = executor.run();
+        assert_eq!(paths.len(), 1);
+
+        let path = &paths[0];
+        let result = path.read(0);
+        assert_eq!(path.end, Ok(State::Halted));
+        assert_eq!(result.to_string(), "noun * 7 + verb");
+        assert_eq!(result.linear().unwrap().to_string(), "7*noun + verb");
+        assert_eq!(path.read(3).to_string(), "noun + verb");
+
+        let ranges = [("noun", 0..=99), ("verb", 0..=20)];
+        let values = solve(path, &result, 89, &ranges).unwrap().unwrap();
+        assert_eq!((values["noun"], values["verb"]), (10, 19));
+
+        let mut machine = Machine::new(NOUN_VERB);
+        machine.write(1, 10).unwrap();
+        machine.write(2, 19).unwrap();
+        machine.execute(&mut QueueTerminal::default()).unwrap();
+        assert_eq!(machine.read(0), 89);
+
+        assert_eq!(solve(path, &result, 1000, &ranges), Ok(None));
+        assert_eq!(
+            solve(path, &result, 89, &ranges[..1]),
+            Err(SolveError::Unbounded("verb".to_string()))
+        );
+    }
+
+    #[test]
+    fn branches() {
+        let mut executor = Executor::new(&BRANCH);
+        executor.input(Expr::symbol("x"));
+
+        let paths = executor.run();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].constraints[0].to_string(), "x < 5 != 0");
+        assert_eq!(paths[0].outputs[0].to_string(), "2 * x");
+        assert_eq!(paths[1].constraints[0].to_string(), "x < 5 == 0");
+        assert_eq!(paths[1].outputs[0].to_string(), "x + 100");
+
+        let ranges = [("x", -200..=200)];
+        let small = solve(&paths[0], &paths[0].outputs[0], 8, &ranges).unwrap();
+        assert_eq!(small.map(|values| values["x"]), Some(4));
+
+        // x + 100 = 8 needs x = -92, which takes the other branch.
+        assert_eq!(solve(&paths[1], &paths[1].outputs[0], 8, &ranges), Ok(None));
+        let large = solve(&paths[1], &paths[1].outputs[0], 150, &ranges).unwrap();
+        assert_eq!(large.map(|values| values["x"]), Some(50));
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut executor = Executor::new(&[3, 2, 99]);
+        assert_eq!(executor.run()[0].end, Ok(State::NeedsInput));
+
+        executor.input(Expr::symbol("x"));
+        assert_eq!(
+            executor.run()[0].end,
+            Err(SymbolicError::SymbolicInstruction { pc: 2 })
+        );
+
+        let mut executor = Executor::new(&[3, 2, 0]);
+        executor.input(Expr::Const(42));
+        assert_eq!(
+            executor.run()[0].end,
+            Err(SymbolicError::Machine(MachineError::UnknownOpCode {
+                pc: 2,
+                value: 42
+            }))
+        );
+
+        // Writes through its input as an address.
+        let mut executor = Executor::new(&[3, 5, 1, 0, 0, 0, 99]);
+        executor.input(Expr::symbol("x"));
+        assert_eq!(
+            executor.run()[0].end,
+            Err(SymbolicError::SymbolicAddress { pc: 2 })
+        );
+    }
+
+    #[cfg(feature = "smt")]
+    #[test]
+    fn smt_script() {
+        use super::smt::{parse, script};
+
+        let mut executor = Executor::new(&BRANCH);
+        executor.input(Expr::symbol("x"));
+        let path = &executor.run()[0];
+
+        assert_eq!(
+            script(path, &path.outputs[0], 8, &[("x", -200..=200)]).unwrap(),
+            "(set-logic ALL)\n\
+             (declare-const |x| Int)\n\
+             (assert (<= (- 200) |x| 200))\n\
+             (assert (not (= (ite (< |x| 5) 1 0) 0)))\n\
+             (assert (= (* 2 |x|) 8))\n\
+             (check-sat)\n\
+             (get-value (|x|))\n"
+        );
+
+        let values = parse("sat\n((|x| 4)\n (|y| (- 3)))\n").unwrap().unwrap();
+        assert_eq!((values["x"], values["y"]), (4, -3));
+        assert_eq!(parse("unsat\n"), Ok(None));
+    }
+}
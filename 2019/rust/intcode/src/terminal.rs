@@ -0,0 +1,202 @@
+use crate::word::Word;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+/// Source of input values and sink of output values for a [`Machine`](crate::Machine).
+pub trait Terminal<W = i64> {
+    /// Returns the next input value, or `None` if the machine should pause until one arrives.
+    fn input(&mut self) -> Option<W>;
+    fn output(&mut self, val: W);
+}
+
+/// Reads one integer per line from stdin and prints every output on its own line.
+///
+/// End of input pauses the machine.
+pub struct CommandLineTerminal {}
+
+impl<W: Word> Terminal<W> for CommandLineTerminal {
+    fn input(&mut self) -> Option<W> {
+        let mut val = String::new();
+        let read = io::stdin()
+            .read_line(&mut val)
+            .expect("Failed to read from stdin");
+
+        if read == 0 {
+            return None;
+        }
+
+        Some(W::from_str_radix(val.trim(), 10).unwrap_or_else(|_| panic!("Not an integer")))
+    }
+
+    fn output(&mut self, val: W) {
+        println!("{}", val);
+    }
+}
+
+/// Feeds queued inputs to the machine and collects its outputs.
+///
+/// Running out of inputs pauses the machine, which makes this the glue between machines that
+/// pass values to each other.
+#[derive(Debug)]
+pub struct QueueTerminal<W = i64> {
+    pub inputs: VecDeque<W>,
+    pub outputs: VecDeque<W>,
+}
+
+impl<W: Clone> QueueTerminal<W> {
+    pub fn new(inputs: &[W]) -> QueueTerminal<W> {
+        QueueTerminal {
+            inputs: inputs.iter().cloned().collect(),
+            outputs: VecDeque::new(),
+        }
+    }
+}
+
+impl<W> Default for QueueTerminal<W> {
+    fn default() -> QueueTerminal<W> {
+        QueueTerminal {
+            inputs: VecDeque::new(),
+            outputs: VecDeque::new(),
+        }
+    }
+}
+
+impl<W> Terminal<W> for QueueTerminal<W> {
+    fn input(&mut self) -> Option<W> {
+        self.inputs.pop_front()
+    }
+
+    fn output(&mut self, val: W) {
+        self.outputs.push_back(val);
+    }
+}
+
+/// Talks to programs that speak ASCII: output codes are collected into lines of text and each
+/// line of input is sent as its character codes followed by 10.
+///
+/// Output values that are not ASCII codes, like the large number many puzzles end with, are
+/// written as numbers on a line of their own. Lines queued with [`AsciiTerminal::script`] are
+/// sent before anything is read from `input` and echoed to `output`, so a transcript reads as if
+/// they had been typed. End of input pauses the machine.
+pub struct AsciiTerminal<R, O> {
+    input: R,
+    output: O,
+    script: VecDeque<String>,
+    pending: VecDeque<u8>,
+    line: String,
+}
+
+impl<R: BufRead, O: Write> AsciiTerminal<R, O> {
+    pub fn new(input: R, output: O) -> AsciiTerminal<R, O> {
+        AsciiTerminal {
+            input,
+            output,
+            script: VecDeque::new(),
+            pending: VecDeque::new(),
+            line: String::new(),
+        }
+    }
+
+    /// Queues every line of `script` to be sent before reading from the input.
+    pub fn script(&mut self, script: &str) {
+        self.script.extend(script.lines().map(String::from));
+    }
+
+    /// Writes out the last line of output even though the program has not ended it yet.
+    pub fn flush(&mut self) {
+        if !self.line.is_empty() {
+            writeln!(self.output, "{}", self.line).expect("Failed to write output");
+            self.line.clear();
+        }
+
+        self.output.flush().expect("Failed to write output");
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        if let Some(line) = self.script.pop_front() {
+            writeln!(self.output, "{}", line).expect("Failed to write output");
+            return Some(line);
+        }
+
+        // Input that cannot be read, like a line that is not UTF-8, ends it just as EOF does.
+        let mut line = String::new();
+
+        if let Ok(0) | Err(_) = self.input.read_line(&mut line) {
+            return None;
+        }
+
+        Some(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+    }
+}
+
+impl<R: BufRead, O: Write, W: Word> Terminal<W> for AsciiTerminal<R, O> {
+    fn input(&mut self) -> Option<W> {
+        if self.pending.is_empty() {
+            // Whatever the program printed so far is the prompt for this line, and what is
+            // typed goes on the same line.
+            write!(self.output, "{}", self.line).expect("Failed to write output");
+            self.line.clear();
+            self.output.flush().expect("Failed to write output");
+
+            let line = self.next_line()?;
+            self.pending.extend(line.bytes());
+            self.pending.push_back(b'\n');
+        }
+
+        self.pending.pop_front().and_then(W::from_u8)
+    }
+
+    fn output(&mut self, val: W) {
+        match val.to_u8().filter(u8::is_ascii) {
+            Some(b'\n') => {
+                writeln!(self.output, "{}", self.line).expect("Failed to write output");
+                self.line.clear();
+            }
+            Some(code) => self.line.push(code as char),
+            None => {
+                self.flush();
+                writeln!(self.output, "{}", val).expect("Failed to write output");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsciiTerminal, Terminal};
+
+    fn codes(text: &str) -> Vec<i64> {
+        text.bytes().map(i64::from).collect()
+    }
+
+    #[test]
+    fn ascii_lines() {
+        let mut output = Vec::new();
+        let mut term = AsciiTerminal::new("north\r\ntake\n".as_bytes(), &mut output);
+        term.script("inv");
+
+        for code in codes("Command?\n> ") {
+            term.output(code);
+        }
+
+        let typed: Vec<i64> = (0..15).map_while(|_| term.input()).collect();
+        assert_eq!(typed, codes("inv\nnorth\ntake\n"));
+        assert_eq!(Terminal::<i64>::input(&mut term), None);
+
+        term.output(10);
+        term.output(i64::from(b'!'));
+        term.output(-1);
+        term.output(19_358_688);
+        term.output(i64::from(b'x'));
+        term.flush();
+
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text, "Command?\n> inv\n\n!\n-1\n19358688\nx\n");
+    }
+
+    #[test]
+    fn unreadable_input() {
+        let mut term = AsciiTerminal::new(&b"\xff\n"[..], Vec::new());
+        assert_eq!(Terminal::<i64>::input(&mut term), None);
+    }
+}
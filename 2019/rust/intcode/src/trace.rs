@@ -0,0 +1,447 @@
+//! Opt-in execution tracing and profiling.
+//!
+//! A [`Tracer`] drives a machine in place of [`Machine::resume`] and friends. It always keeps
+//! a [`Profile`] and can additionally stream one [`TraceRecord`] per executed instruction as
+//! JSON lines or in a compact binary format read back by [`read_binary`].
+
+use crate::error::MachineError;
+use crate::instruction::{Instruction, Parameter};
+use crate::machine::{Machine, MemoryWrite, State};
+use crate::opcode::{OpCode, ParameterMode};
+use crate::terminal::Terminal;
+use crate::varint;
+use num_traits::FromPrimitive;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, Read, Write};
+
+const BINARY_MAGIC: &[u8] = b"ICTR\x01";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceFormat {
+    Jsonl,
+    Binary,
+}
+
+/// One executed instruction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceRecord {
+    /// Number of instructions the machine had executed before this one.
+    pub step: u64,
+    pub instruction: Instruction,
+    pub write: Option<MemoryWrite>,
+}
+
+impl TraceRecord {
+    fn write_jsonl(&self, out: &mut dyn Write) -> io::Result<()> {
+        let operands: Vec<String> = self
+            .instruction
+            .parameters
+            .iter()
+            .map(|parameter| format!("\"{}\"", parameter))
+            .collect();
+
+        write!(
+            out,
+            "{{\"step\":{},\"pc\":{},\"op\":\"{}\",\"operands\":[{}]",
+            self.step,
+            self.instruction.address,
+            self.instruction.opcode.mnemonic(),
+            operands.join(",")
+        )?;
+
+        if let Some(write) = &self.write {
+            write!(
+                out,
+                ",\"write\":{{\"address\":{},\"previous\":{},\"value\":{}}}",
+                write.address, write.previous, write.value
+            )?;
+        }
+
+        writeln!(out, "}}")
+    }
+
+    fn write_binary(&self, mut out: &mut dyn Write) -> io::Result<()> {
+        varint::write_unsigned(&mut out, self.step)?;
+        varint::write_unsigned(&mut out, self.instruction.address as u64)?;
+        out.write_all(&[self.instruction.opcode as u8])?;
+
+        for parameter in &self.instruction.parameters {
+            out.write_all(&[parameter.mode as u8])?;
+            varint::write_signed(&mut out, parameter.value)?;
+        }
+
+        match &self.write {
+            Some(write) => {
+                out.write_all(&[1])?;
+                varint::write_unsigned(&mut out, write.address as u64)?;
+                varint::write_signed(&mut out, write.previous)?;
+                varint::write_signed(&mut out, write.value)
+            }
+            None => out.write_all(&[0]),
+        }
+    }
+
+    fn read_binary(input: &mut impl Read) -> io::Result<Option<TraceRecord>> {
+        let invalid = |what| io::Error::new(io::ErrorKind::InvalidData, what);
+        let byte = |input: &mut dyn Read| -> io::Result<u8> {
+            let mut byte = [0u8];
+            input.read_exact(&mut byte)?;
+            Ok(byte[0])
+        };
+
+        let step = match varint::read_unsigned(input)? {
+            Some(step) => step,
+            None => return Ok(None),
+        };
+        let address = varint::expect_unsigned(input)? as usize;
+        let opcode: OpCode =
+            FromPrimitive::from_u8(byte(input)?).ok_or_else(|| invalid("unknown opcode"))?;
+
+        let parameters = (0..opcode.parameters())
+            .map(|_| {
+                let mode: ParameterMode =
+                    FromPrimitive::from_u8(byte(input)?).ok_or_else(|| invalid("unknown mode"))?;
+                let value = varint::expect_signed(input)?;
+                Ok(Parameter { mode, value })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let write = match byte(input)? {
+            0 => None,
+            1 => Some(MemoryWrite {
+                address: varint::expect_unsigned(input)? as usize,
+                previous: varint::expect_signed(input)?,
+                value: varint::expect_signed(input)?,
+            }),
+            _ => return Err(invalid("bad write flag")),
+        };
+
+        Ok(Some(TraceRecord {
+            step,
+            instruction: Instruction {
+                address,
+                opcode,
+                parameters,
+            },
+            write,
+        }))
+    }
+}
+
+/// Reads back a trace written in [`TraceFormat::Binary`].
+pub fn read_binary(mut input: impl Read) -> io::Result<Vec<TraceRecord>> {
+    let mut magic = [0u8; 5];
+    input.read_exact(&mut magic)?;
+
+    if magic != BINARY_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an Intcode trace",
+        ));
+    }
+
+    let mut records = Vec::new();
+
+    while let Some(record) = TraceRecord::read_binary(&mut input)? {
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Aggregate counts over everything a [`Tracer`] has executed.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub steps: u64,
+    /// Instructions executed by each machine that ran to its `End` instruction.
+    pub runs: Vec<u64>,
+    pub opcodes: BTreeMap<OpCode, u64>,
+    pub pcs: HashMap<usize, u64>,
+    /// Jumps taken backwards, keyed by (jump address, target), which is where loops close.
+    pub back_jumps: HashMap<(usize, usize), u64>,
+}
+
+impl Profile {
+    fn record(&mut self, instruction: &Instruction, next_pc: usize) {
+        self.steps += 1;
+        *self.opcodes.entry(instruction.opcode).or_insert(0) += 1;
+        *self.pcs.entry(instruction.address).or_insert(0) += 1;
+
+        if next_pc <= instruction.address {
+            *self
+                .back_jumps
+                .entry((instruction.address, next_pc))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// The `count` most executed instruction addresses, busiest first.
+    pub fn hottest_pcs(&self, count: usize) -> Vec<(usize, u64)> {
+        hottest(&self.pcs, count)
+    }
+
+    /// The `count` most taken backward jumps, busiest first.
+    pub fn hottest_loops(&self, count: usize) -> Vec<((usize, usize), u64)> {
+        hottest(&self.back_jumps, count)
+    }
+
+    pub fn merge(&mut self, other: &Profile) {
+        self.steps += other.steps;
+        self.runs.extend(other.runs.iter().cloned());
+
+        for (opcode, count) in &other.opcodes {
+            *self.opcodes.entry(*opcode).or_insert(0) += count;
+        }
+
+        for (pc, count) in &other.pcs {
+            *self.pcs.entry(*pc).or_insert(0) += count;
+        }
+
+        for (edge, count) in &other.back_jumps {
+            *self.back_jumps.entry(*edge).or_insert(0) += count;
+        }
+    }
+}
+
+fn hottest<K: Copy + Ord>(counts: &HashMap<K, u64>, count: usize) -> Vec<(K, u64)> {
+    let mut entries: Vec<(K, u64)> = counts.iter().map(|(&k, &n)| (k, n)).collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    entries.truncate(count);
+    entries
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "steps: {}", self.steps)?;
+
+        if let (Some(min), Some(max)) = (self.runs.iter().min(), self.runs.iter().max()) {
+            let total: u64 = self.runs.iter().sum();
+            write!(
+                f,
+                " over {} runs (min {}, mean {}, max {})",
+                self.runs.len(),
+                min,
+                total / self.runs.len() as u64,
+                max
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "\n{:6}  {:>10}  {:>6}", "opcode", "count", "share")?;
+
+        for (opcode, count) in &self.opcodes {
+            let share = 100.0 * *count as f64 / self.steps.max(1) as f64;
+            writeln!(f, "{:6}  {:10}  {:5.1}%", opcode.mnemonic(), count, share)?;
+        }
+
+        writeln!(f, "\n{:4}  {:>10}", "pc", "count")?;
+
+        for (pc, count) in self.hottest_pcs(10) {
+            writeln!(f, "{:04}  {:10}", pc, count)?;
+        }
+
+        writeln!(f, "\n{:12}  {:>10}", "loop", "count")?;
+
+        for ((from, to), count) in self.hottest_loops(10) {
+            writeln!(f, "{:04} -> {:04}  {:10}", from, to, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs machines while recording what they execute.
+#[derive(Default)]
+pub struct Tracer {
+    pub profile: Profile,
+    output: Option<(TraceFormat, Box<dyn Write>)>,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    /// A tracer that only keeps a profile.
+    pub fn new() -> Tracer {
+        Tracer::default()
+    }
+
+    /// A tracer that also streams every instruction to `out`.
+    pub fn with_output(format: TraceFormat, out: impl Write + 'static) -> Tracer {
+        let mut tracer = Tracer {
+            output: Some((format, Box::new(out))),
+            ..Tracer::default()
+        };
+
+        if format == TraceFormat::Binary {
+            tracer.emit(|out| out.write_all(BINARY_MAGIC));
+        }
+
+        tracer
+    }
+
+    fn emit(&mut self, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) {
+        if let (Some((_, out)), None) = (&mut self.output, &self.error) {
+            if let Err(error) = write(out.as_mut()) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Executes one instruction, like [`Machine::step`].
+    pub fn step(
+        &mut self,
+        machine: &mut Machine,
+        term: &mut impl Terminal,
+    ) -> Result<State, MachineError> {
+        let instruction = Instruction::fetch(machine.memory(), machine.pc());
+        let step = machine.steps();
+        let state = machine.step(term)?;
+
+        if let (State::Running, Some(instruction)) = (&state, instruction) {
+            self.profile.record(&instruction, machine.pc());
+
+            if machine.terminated() {
+                self.profile.runs.push(machine.steps());
+            }
+
+            let record = TraceRecord {
+                step,
+                instruction,
+                write: machine.last_write().cloned(),
+            };
+
+            match self.output.as_ref().map(|(format, _)| *format) {
+                Some(TraceFormat::Jsonl) => self.emit(|out| record.write_jsonl(out)),
+                Some(TraceFormat::Binary) => self.emit(|out| record.write_binary(out)),
+                None => {}
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Like [`Machine::resume`], tracing every instruction.
+    pub fn resume(
+        &mut self,
+        machine: &mut Machine,
+        term: &mut impl Terminal,
+    ) -> Result<State, MachineError> {
+        loop {
+            match self.step(machine, term)? {
+                State::Running => continue,
+                state => break Ok(state),
+            }
+        }
+    }
+
+    /// Like [`Machine::execute`], tracing every instruction.
+    pub fn execute(
+        &mut self,
+        machine: &mut Machine,
+        term: &mut impl Terminal,
+    ) -> Result<(), MachineError> {
+        match self.resume(machine, term)? {
+            State::NeedsInput => Err(MachineError::InputExhausted { pc: machine.pc() }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Flushes the trace output and returns the profile, or the first write error.
+    pub fn finish(mut self) -> io::Result<Profile> {
+        self.emit(|out| out.flush());
+
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.profile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_binary, TraceFormat, Tracer};
+    use crate::examples::COUNTDOWN;
+    use crate::machine::{Machine, MemoryWrite};
+    use crate::opcode::OpCode;
+    use crate::terminal::QueueTerminal;
+    use std::cell::RefCell;
+    use std::io::{self, Write};
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn profile() {
+        let mut tracer = Tracer::new();
+        let mut term = QueueTerminal::new(&[3]);
+        tracer
+            .execute(&mut Machine::new(COUNTDOWN), &mut term)
+            .unwrap();
+
+        let profile = tracer.finish().unwrap();
+        assert_eq!(profile.steps, 10);
+        assert_eq!(profile.runs, [10]);
+        assert_eq!(profile.opcodes[&OpCode::Output], 3);
+        assert_eq!(profile.opcodes[&OpCode::JumpIfTrue], 3);
+        assert_eq!(profile.hottest_pcs(1), [(2, 3)]);
+        assert_eq!(profile.hottest_loops(1), [((8, 2), 2)]);
+    }
+
+    #[test]
+    fn jsonl() {
+        let out = Shared::default();
+        let mut tracer = Tracer::with_output(TraceFormat::Jsonl, out.clone());
+        let mut term = QueueTerminal::new(&[1]);
+        tracer
+            .execute(&mut Machine::new(COUNTDOWN), &mut term)
+            .unwrap();
+        tracer.finish().unwrap();
+
+        let text = String::from_utf8(out.0.borrow().clone()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            r#"{"step":0,"pc":0,"op":"in","operands":["[12]"],"write":{"address":12,"previous":0,"value":1}}"#
+        );
+        assert_eq!(
+            lines[3],
+            r##"{"step":3,"pc":8,"op":"jt","operands":["[12]","#2"]}"##
+        );
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let out = Shared::default();
+        let mut tracer = Tracer::with_output(TraceFormat::Binary, out.clone());
+        let mut term = QueueTerminal::new(&[2]);
+        tracer
+            .execute(&mut Machine::new(COUNTDOWN), &mut term)
+            .unwrap();
+        tracer.finish().unwrap();
+
+        let records = read_binary(&out.0.borrow()[..]).unwrap();
+        assert_eq!(records.len(), 7);
+        assert_eq!(records[6].step, 6);
+        assert_eq!(records[6].instruction.to_string(), "JT [12], #2");
+        assert_eq!(
+            records[5].write,
+            Some(MemoryWrite {
+                address: 12,
+                previous: 1,
+                value: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! LEB128 variable-length integers, zigzag-encoded for signed values.
+
+use std::io::{self, Read, Write};
+
+pub fn write_unsigned(out: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            return out.write_all(&[byte]);
+        }
+
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub fn write_signed(out: &mut impl Write, value: i64) -> io::Result<()> {
+    write_unsigned(out, ((value << 1) ^ (value >> 63)) as u64)
+}
+
+/// Reads an unsigned varint, or `None` at a clean end of input.
+pub fn read_unsigned(input: &mut impl Read) -> io::Result<Option<u64>> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    let mut byte = [0u8];
+
+    loop {
+        if input.read(&mut byte)? == 0 {
+            return if shift == 0 {
+                Ok(None)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated varint",
+                ))
+            };
+        }
+
+        if shift >= 64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "varint too long",
+            ));
+        }
+
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        shift += 7;
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+}
+
+pub fn read_signed(input: &mut impl Read) -> io::Result<Option<i64>> {
+    Ok(read_unsigned(input)?.map(|value| ((value >> 1) as i64) ^ -((value & 1) as i64)))
+}
+
+/// Like [`read_unsigned`] but treats end of input as an error.
+pub fn expect_unsigned(input: &mut impl Read) -> io::Result<u64> {
+    read_unsigned(input)?.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated"))
+}
+
+/// Like [`read_signed`] but treats end of input as an error.
+pub fn expect_signed(input: &mut impl Read) -> io::Result<i64> {
+    read_signed(input)?.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_signed, write_signed};
+
+    #[test]
+    fn round_trip() {
+        let values = [
+            0,
+            1,
+            -1,
+            63,
+            -64,
+            64,
+            1_125_899_906_842_624,
+            i64::MIN,
+            i64::MAX,
+        ];
+        let mut bytes = Vec::new();
+
+        for &value in values.iter() {
+            write_signed(&mut bytes, value).unwrap();
+        }
+
+        assert_eq!(bytes[..3], [0, 2, 1]);
+
+        let mut input = &bytes[..];
+        for &value in values.iter() {
+            assert_eq!(read_signed(&mut input).unwrap(), Some(value));
+        }
+        assert_eq!(read_signed(&mut input).unwrap(), None);
+    }
+}
@@ -0,0 +1,486 @@
+//! Memory watchpoints and access heatmaps.
+//!
+//! A [`Watcher`] drives a machine in place of [`Machine::resume`], like a
+//! [`Tracer`](crate::trace::Tracer) does. It counts every read and write of a memory cell into a
+//! [`Heatmap`] and checks them against its watchpoints, which either pause the run or just log
+//! the access.
+//!
+//! Only operands count as reads: fetching the instruction itself does not.
+
+use crate::error::MachineError;
+use crate::instruction::Instruction;
+use crate::machine::{Machine, State};
+use crate::opcode::ParameterMode;
+use crate::terminal::Terminal;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fmt::Write as _;
+
+/// Shades for a cell in the terminal heatmap, from untouched to busiest.
+const RAMP: &[u8] = b" .:-=+*#%@";
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Which accesses a watchpoint fires on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Watch {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Watch {
+    pub fn covers(self, access: Access) -> bool {
+        match self {
+            Watch::Read => access == Access::Read,
+            Watch::Write => access == Access::Write,
+            Watch::ReadWrite => true,
+        }
+    }
+}
+
+impl fmt::Display for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Watch::Read => "r",
+            Watch::Write => "w",
+            Watch::ReadWrite => "rw",
+        })
+    }
+}
+
+/// What a watchpoint does when it fires.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// Stop the run after the instruction that made the access.
+    Pause,
+    /// Add the access to [`Watcher::log`] and carry on.
+    Log,
+}
+
+/// An access that set off a watchpoint.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hit {
+    /// Number of instructions the machine had executed before the one making the access.
+    pub step: u64,
+    pub pc: usize,
+    pub address: usize,
+    pub access: Access,
+    /// The value read, or the value written.
+    pub value: i64,
+}
+
+impl fmt::Display for Hit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = match self.access {
+            Access::Read => "reads",
+            Access::Write => "writes",
+        };
+
+        write!(
+            f,
+            "step {}: {:04} {} [{:04}] = {}",
+            self.step, self.pc, verb, self.address, self.value
+        )
+    }
+}
+
+/// Addresses `instruction` reads its operands from, given the relative base it runs with.
+/// Operands that would fail with a negative address are left out.
+pub fn reads(instruction: &Instruction, relative_base: i64) -> Vec<usize> {
+    let target = instruction.opcode.target();
+
+    instruction
+        .parameters
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| Some(i + 1) != target)
+        .filter_map(|(_, parameter)| match parameter.mode {
+            ParameterMode::Position => Some(parameter.value),
+            ParameterMode::Relative => relative_base.checked_add(parameter.value),
+            ParameterMode::Immediate => None,
+        })
+        .filter(|&address| address >= 0)
+        .map(|address| address as usize)
+        .collect()
+}
+
+/// How often each memory cell was read and written.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Heatmap {
+    pub reads: HashMap<usize, u64>,
+    pub writes: HashMap<usize, u64>,
+}
+
+impl Heatmap {
+    pub fn record(&mut self, address: usize, access: Access) {
+        let counts = match access {
+            Access::Read => &mut self.reads,
+            Access::Write => &mut self.writes,
+        };
+
+        *counts.entry(address).or_insert(0) += 1;
+    }
+
+    pub fn merge(&mut self, other: &Heatmap) {
+        for (address, count) in &other.reads {
+            *self.reads.entry(*address).or_insert(0) += count;
+        }
+
+        for (address, count) in &other.writes {
+            *self.writes.entry(*address).or_insert(0) += count;
+        }
+    }
+
+    /// One past the highest address accessed.
+    pub fn len(&self) -> usize {
+        self.reads
+            .keys()
+            .chain(self.writes.keys())
+            .max()
+            .map_or(0, |&address| address + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reads.is_empty() && self.writes.is_empty()
+    }
+
+    /// The `count` most written addresses, busiest first, which is usually where a program
+    /// keeps its state.
+    pub fn hottest_writes(&self, count: usize) -> Vec<(usize, u64)> {
+        let mut entries: Vec<(usize, u64)> = self.writes.iter().map(|(&a, &n)| (a, n)).collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        entries.truncate(count);
+        entries
+    }
+
+    /// Shades cells by their count in `counts`, from 0 for untouched to `levels - 1` for the
+    /// busiest, on a log scale so that a hot loop counter does not wash everything else out.
+    fn shader(counts: &HashMap<usize, u64>, levels: usize) -> impl Fn(usize) -> usize + '_ {
+        let max = counts.values().max().cloned().unwrap_or(0);
+
+        move |address| match counts.get(&address) {
+            None => 0,
+            Some(_) if max <= 1 => levels - 1,
+            Some(&count) => {
+                let share = (count as f64).ln() / (max as f64).ln();
+                1 + (share * (levels - 2) as f64).round() as usize
+            }
+        }
+    }
+
+    /// Renders reads and writes side by side as character grids `width` cells wide, one row
+    /// per `width` addresses. A width of zero is taken as one.
+    pub fn render(&self, width: usize) -> String {
+        let width = width.max(1);
+        let mut out = String::new();
+        let (read, written) = (
+            Heatmap::shader(&self.reads, RAMP.len()),
+            Heatmap::shader(&self.writes, RAMP.len()),
+        );
+
+        writeln!(out, "{:4}  {:width$}  writes", "", "reads", width = width).unwrap();
+
+        for row in (0..self.len()).step_by(width) {
+            let reads: String = (row..row + width)
+                .map(|address| RAMP[read(address)] as char)
+                .collect();
+            let writes: String = (row..row + width)
+                .map(|address| RAMP[written(address)] as char)
+                .collect();
+
+            let line = format!("{:04}  {}  {}", row, reads, writes);
+            writeln!(out, "{}", line.trim_end()).unwrap();
+        }
+
+        out
+    }
+
+    /// Writes the heatmap as a PNG with a square of `scale` pixels per cell, `width` cells to a
+    /// row. Reads show in green and writes in red, so cells that are both come out yellow.
+    /// A width or scale of zero is taken as one.
+    #[cfg(feature = "png")]
+    pub fn write_png(
+        &self,
+        width: usize,
+        scale: usize,
+        out: impl std::io::Write,
+    ) -> Result<(), png::EncodingError> {
+        let (width, scale) = (width.max(1), scale.max(1));
+        let rows = self.len().div_ceil(width).max(1);
+        let (columns, lines) = (width * scale, rows * scale);
+        let mut pixels = vec![0u8; columns * lines * 3];
+        let (read, written) = (
+            Heatmap::shader(&self.reads, 256),
+            Heatmap::shader(&self.writes, 256),
+        );
+
+        for address in 0..self.len() {
+            let colour = [written(address) as u8, read(address) as u8, 0];
+            let (x, y) = ((address % width) * scale, (address / width) * scale);
+
+            for line in y..y + scale {
+                for column in x..x + scale {
+                    let pixel = (line * columns + column) * 3;
+                    pixels[pixel..pixel + 3].copy_from_slice(&colour);
+                }
+            }
+        }
+
+        let mut encoder = png::Encoder::new(out, columns as u32, lines as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&pixels)
+    }
+}
+
+impl fmt::Display for Heatmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reads: u64 = self.reads.values().sum();
+        let writes: u64 = self.writes.values().sum();
+
+        writeln!(
+            f,
+            "{} reads of {} cells, {} writes to {} cells\n",
+            reads,
+            self.reads.len(),
+            writes,
+            self.writes.len()
+        )?;
+        write!(f, "{}", self.render(50))?;
+        writeln!(f, "\n{:4}  {:>10}", "cell", "writes")?;
+
+        for (address, count) in self.hottest_writes(10) {
+            writeln!(f, "{:04}  {:10}", address, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Why [`Watcher::step`] or [`Watcher::resume`] returned.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Stop {
+    State(State),
+    /// A watchpoint with [`Action::Pause`] fired. The instruction that set it off has run.
+    Paused(Hit),
+}
+
+/// Runs machines while counting and watching their memory accesses.
+#[derive(Clone, Debug, Default)]
+pub struct Watcher {
+    pub heatmap: Heatmap,
+    /// Hits of logging watchpoints, oldest first.
+    pub log: Vec<Hit>,
+    watchpoints: BTreeMap<usize, (Watch, Action)>,
+}
+
+impl Watcher {
+    pub fn new() -> Watcher {
+        Watcher::default()
+    }
+
+    /// Watches `address`, replacing any watchpoint already there.
+    pub fn add(&mut self, address: usize, watch: Watch, action: Action) {
+        self.watchpoints.insert(address, (watch, action));
+    }
+
+    /// Removes the watchpoint at `address`, returning whether there was one.
+    pub fn remove(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address).is_some()
+    }
+
+    /// The watchpoint at `address`, if there is one.
+    pub fn watchpoint(&self, address: usize) -> Option<(Watch, Action)> {
+        self.watchpoints.get(&address).cloned()
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = (usize, Watch, Action)> + '_ {
+        self.watchpoints
+            .iter()
+            .map(|(&address, &(watch, action))| (address, watch, action))
+    }
+
+    /// Executes one instruction, like [`Machine::step`]. When several pausing watchpoints fire
+    /// at once the first one stops the run and the others are logged.
+    pub fn step(
+        &mut self,
+        machine: &mut Machine,
+        term: &mut impl Terminal,
+    ) -> Result<Stop, MachineError> {
+        let step = machine.steps();
+        let pc = machine.pc();
+        let reads: Vec<(usize, i64)> = Instruction::fetch(machine.memory(), pc)
+            .map(|instruction| reads(&instruction, machine.relative_base()))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|address| (address, machine.read(address)))
+            .collect();
+
+        let state = machine.step(term)?;
+
+        if state != State::Running {
+            return Ok(Stop::State(state));
+        }
+
+        let write = machine
+            .last_write()
+            .map(|write| (write.address, Access::Write, write.value));
+        let accesses = reads
+            .into_iter()
+            .map(|(address, value)| (address, Access::Read, value))
+            .chain(write);
+        let mut paused = None;
+
+        for (address, access, value) in accesses {
+            self.heatmap.record(address, access);
+
+            let action = match self.watchpoints.get(&address) {
+                Some(&(watch, action)) if watch.covers(access) => action,
+                _ => continue,
+            };
+            let hit = Hit {
+                step,
+                pc,
+                address,
+                access,
+                value,
+            };
+
+            match action {
+                Action::Pause if paused.is_none() => paused = Some(hit),
+                _ => self.log.push(hit),
+            }
+        }
+
+        Ok(match paused {
+            Some(hit) => Stop::Paused(hit),
+            None => Stop::State(State::Running),
+        })
+    }
+
+    /// Like [`Machine::resume`], stopping early at a pausing watchpoint.
+    pub fn resume(
+        &mut self,
+        machine: &mut Machine,
+        term: &mut impl Terminal,
+    ) -> Result<Stop, MachineError> {
+        loop {
+            match self.step(machine, term)? {
+                Stop::State(State::Running) => continue,
+                stop => break Ok(stop),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reads, Access, Action, Hit, Stop, Watch, Watcher};
+    use crate::examples::COUNTDOWN;
+    use crate::instruction::Instruction;
+    use crate::machine::{Machine, State};
+    use crate::terminal::QueueTerminal;
+
+    #[test]
+    fn operand_reads() {
+        let add = Instruction::decode(&[1201, 26, -4, 26], 0).unwrap();
+        assert_eq!(reads(&add, 10), [36]);
+        assert_eq!(reads(&add, -30), []);
+
+        let jump = Instruction::decode(&[5, 3, 7], 0).unwrap();
+        assert_eq!(reads(&jump, 0), [3, 7]);
+    }
+
+    #[test]
+    fn watchpoints() {
+        let mut watcher = Watcher::new();
+        let mut machine = Machine::new(COUNTDOWN);
+        let mut term = QueueTerminal::new(&[2]);
+
+        watcher.add(12, Watch::Write, Action::Pause);
+        assert_eq!(
+            watcher.resume(&mut machine, &mut term),
+            Ok(Stop::Paused(Hit {
+                step: 0,
+                pc: 0,
+                address: 12,
+                access: Access::Write,
+                value: 2,
+            }))
+        );
+
+        watcher.add(12, Watch::ReadWrite, Action::Log);
+        assert_eq!(
+            watcher.resume(&mut machine, &mut term),
+            Ok(Stop::State(State::Halted))
+        );
+        assert_eq!(term.outputs, [2, 1]);
+
+        // A read by the output, a read and a write by the decrement and a read by the jump,
+        // for each of the two values.
+        assert_eq!(watcher.log.len(), 8);
+        assert_eq!(watcher.log[2].to_string(), "step 2: 0004 writes [0012] = 1");
+
+        assert!(watcher.remove(12));
+        assert!(!watcher.remove(12));
+        assert_eq!(watcher.watchpoints().count(), 0);
+    }
+
+    #[test]
+    fn heatmap() {
+        let mut watcher = Watcher::new();
+        let mut term = QueueTerminal::new(&[3]);
+        watcher
+            .resume(&mut Machine::new(COUNTDOWN), &mut term)
+            .unwrap();
+
+        let heatmap = &watcher.heatmap;
+        assert_eq!(heatmap.reads[&12], 9);
+        assert_eq!(heatmap.writes[&12], 4);
+        assert_eq!(heatmap.len(), 13);
+        assert_eq!(heatmap.hottest_writes(1), [(12, 4)]);
+        assert_eq!(
+            heatmap.render(10),
+            "      reads       writes\n0000\n0010    @           @\n"
+        );
+        assert_eq!(heatmap.render(0), heatmap.render(1));
+
+        let mut merged = heatmap.clone();
+        merged.merge(heatmap);
+        assert_eq!(merged.reads[&12], 18);
+        assert_eq!(merged.len(), heatmap.len());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png() {
+        let mut watcher = Watcher::new();
+        let mut term = QueueTerminal::new(&[3]);
+        watcher
+            .resume(&mut Machine::new(COUNTDOWN), &mut term)
+            .unwrap();
+
+        let mut image = Vec::new();
+        watcher.heatmap.write_png(10, 2, &mut image).unwrap();
+
+        let mut reader = png::Decoder::new(&image[..]).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (20, 4));
+
+        // Cell 12 is both read and written the most, in the second row.
+        let pixel = (2 * 20 + 2 * 2) * 3;
+        assert_eq!(pixels[pixel..pixel + 3], [255, 255, 0]);
+        assert_eq!(pixels[..3], [0, 0, 0]);
+        let mut image = Vec::new();
+        watcher.heatmap.write_png(0, 0, &mut image).unwrap();
+
+        let reader = png::Decoder::new(&image[..]).read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (1, 13));
+    }
+}
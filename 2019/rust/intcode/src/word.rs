@@ -0,0 +1,30 @@
+use num_traits::{CheckedAdd, CheckedMul, FromPrimitive, Num, Signed, ToPrimitive};
+use std::fmt::{Debug, Display};
+
+/// Value stored in a single memory cell.
+///
+/// Puzzles only ever need `i64`, which is the default everywhere. Enabling the `bigint` feature
+/// lets a machine run on `num_bigint::BigInt` cells for programs that overflow it.
+pub trait Word:
+    Clone
+    + Debug
+    + Display
+    + Ord
+    + Num
+    + Signed
+    + CheckedAdd
+    + CheckedMul
+    + FromPrimitive
+    + ToPrimitive
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+impl Word for i32 {}
+impl Word for i64 {}
+impl Word for i128 {}
+
+#[cfg(feature = "bigint")]
+impl Word for num_bigint::BigInt {}
@@ -0,0 +1,205 @@
+//! Runs every year's puzzles through one interface.
+//!
+//! Each day implements [`Solution`] on top of the library its own binary is built from, and
+//! [`registry`] lists them all with their embedded inputs so the `aoc` binary can find a day by
+//! year and number.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+pub mod y2018;
+pub mod y2019;
+pub mod y2020;
+
+/// What a part of a puzzle comes out as.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// The puzzle has no answer for this input.
+    None,
+    /// The input could not be made sense of.
+    Error(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(number) => write!(f, "{}", number),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::None => write!(f, "-"),
+            Answer::Error(message) => write!(f, "error: {}", message),
+        }
+    }
+}
+
+macro_rules! number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(number: $t) -> Answer {
+                    Answer::Number(number as i64)
+                }
+            }
+        )*
+    };
+}
+
+number!(i32, i64, u32, usize);
+
+impl From<String> for Answer {
+    fn from(text: String) -> Answer {
+        Answer::Text(text)
+    }
+}
+
+impl<T: Into<Answer>> From<Option<T>> for Answer {
+    fn from(answer: Option<T>) -> Answer {
+        answer.map_or(Answer::None, Into::into)
+    }
+}
+
+impl<T: Into<Answer>> From<Result<T, String>> for Answer {
+    fn from(answer: Result<T, String>) -> Answer {
+        answer.map_or_else(Answer::Error, Into::into)
+    }
+}
+
+/// A day's puzzle, given its input as text.
+pub trait Solution {
+    fn part1(input: &str) -> Answer;
+    fn part2(input: &str) -> Answer;
+}
+
+/// A day in the [`registry`].
+#[derive(Clone, Copy)]
+pub struct Entry {
+    pub year: u32,
+    pub day: u32,
+    /// The puzzle input the day's own binaries embed.
+    pub input: &'static str,
+    parts: [fn(&str) -> Answer; 2],
+}
+
+impl Entry {
+    pub fn new<S: Solution>(year: u32, day: u32, input: &'static str) -> Entry {
+        Entry {
+            year,
+            day,
+            input,
+            parts: [S::part1, S::part2],
+        }
+    }
+
+    /// Solves `part`, 1 or 2, timing how long it takes. An [`Answer::Error`] is returned as
+    /// the error.
+    pub fn solve(&self, part: u32, input: &str) -> Result<(Answer, Duration), String> {
+        let at = format!("{} day {} part {}", self.year, self.day, part);
+        let solve = match part {
+            1 | 2 => self.parts[part as usize - 1],
+            _ => return Err(format!("{}: there is no such part", at)),
+        };
+        let start = Instant::now();
+
+        match solve(input) {
+            Answer::Error(message) => Err(format!("{}: {}", at, message)),
+            answer => Ok((answer, start.elapsed())),
+        }
+    }
+}
+
+/// Every day there is a solution for, in order.
+pub fn registry() -> Vec<Entry> {
+    let mut entries = Vec::new();
+    entries.extend(y2018::entries());
+    entries.extend(y2019::entries());
+    entries.extend(y2020::entries());
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{registry, Answer, Entry};
+
+    fn entry(year: u32, day: u32) -> Entry {
+        registry()
+            .into_iter()
+            .find(|entry| entry.year == year && entry.day == day)
+            .expect("No such day")
+    }
+
+    fn answers(year: u32, day: u32) -> (Answer, Answer) {
+        let entry = entry(year, day);
+        let solve = |part| entry.solve(part, entry.input).expect("Solution failed").0;
+
+        (solve(1), solve(2))
+    }
+
+    #[test]
+    fn known_answers() {
+        assert_eq!(
+            answers(2019, 2),
+            (Answer::Number(4_023_471), Answer::Number(8051))
+        );
+        assert_eq!(
+            answers(2019, 5),
+            (Answer::Number(6_069_343), Answer::Number(3_188_550))
+        );
+        assert_eq!(
+            answers(2019, 7),
+            (Answer::Number(24405), Answer::Number(8_271_623))
+        );
+        assert_eq!(
+            answers(2020, 1),
+            (Answer::Number(969_024), Answer::Number(230_057_040))
+        );
+        assert_eq!(answers(2020, 2), (Answer::Number(424), Answer::Number(747)));
+    }
+
+    #[test]
+    fn bad_input() {
+        assert_eq!(
+            entry(2019, 5).solve(1, "1,2,x").map(|(answer, _)| answer),
+            Err(
+                "2019 day 5 part 1: bad program: line 1, column 5: value 2 `x` is not an integer"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn missing_part() {
+        for &part in &[0, 3] {
+            assert_eq!(
+                entry(2020, 1).solve(part, "").map(|(answer, _)| answer),
+                Err(format!("2020 day 1 part {}: there is no such part", part))
+            );
+        }
+    }
+
+    #[test]
+    fn registry_is_ordered() {
+        let days: Vec<_> = registry()
+            .iter()
+            .map(|entry| (entry.year, entry.day))
+            .collect();
+        let mut sorted = days.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        assert_eq!(days, sorted);
+        assert_eq!(days.len(), 13);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Answer::from(-3i32).to_string(), "-3");
+        assert_eq!(Answer::from("abc".to_string()).to_string(), "abc");
+        assert_eq!(Answer::from(None::<u32>), Answer::None);
+        assert_eq!(Answer::None.to_string(), "-");
+        assert_eq!(
+            Answer::from(Err::<u32, _>("bad".to_string())),
+            Answer::Error("bad".to_string())
+        );
+    }
+}
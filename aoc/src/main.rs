@@ -0,0 +1,174 @@
+use aoc::{registry, Answer, Entry};
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: aoc <command> [args...]
+
+Commands:
+    list                        List the days there are solutions for
+    run <year> [day]            Solve a day, or every day of the year with --all, and print
+                                the answers and how long each part took
+
+Options for run:
+    --all                       Run every day of <year>
+    --part <n>                  Only run part 1 or 2
+    --input <file>              Use <file> instead of the embedded input; needs a day";
+
+struct Options {
+    year: u32,
+    day: Option<u32>,
+    all: bool,
+    part: Option<u32>,
+    input: Option<String>,
+}
+
+fn number(arg: &str, what: &str) -> Result<u32, String> {
+    arg.parse()
+        .map_err(|_| format!("{} `{}` is not a number", what, arg))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        year: 0,
+        day: None,
+        all: false,
+        part: None,
+        input: None,
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => options.all = true,
+            "--part" => {
+                let part = args.next().ok_or("--part needs a number")?;
+
+                match number(part, "part")? {
+                    part @ 1..=2 => options.part = Some(part),
+                    _ => return Err(format!("there is no part {}", part)),
+                }
+            }
+            "--input" => {
+                options.input = Some(args.next().ok_or("--input needs a file")?.clone());
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            _ => positional.push(arg),
+        }
+    }
+
+    match positional.as_slice() {
+        [year] => options.year = number(year, "year")?,
+        [year, day] => {
+            options.year = number(year, "year")?;
+            options.day = Some(number(day, "day")?);
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+
+    match (options.day, options.all) {
+        (None, false) => Err("give a day, or --all for every day of the year".to_string()),
+        (Some(_), true) => Err("give a day or --all, not both".to_string()),
+        (None, true) if options.input.is_some() => Err("--input needs a single day".to_string()),
+        _ => Ok(options),
+    }
+}
+
+fn print_table(rows: &[(Entry, u32, Answer, Duration)]) {
+    let answers: Vec<String> = rows.iter().map(|row| row.2.to_string()).collect();
+    let width = answers
+        .iter()
+        .map(|answer| answer.len())
+        .chain(Some("answer".len()))
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "{:>4}  {:>3}  {:>4}  {:<width$}  {:>10}",
+        "year",
+        "day",
+        "part",
+        "answer",
+        "time",
+        width = width
+    );
+
+    for ((entry, part, _, time), answer) in rows.iter().zip(&answers) {
+        println!(
+            "{:>4}  {:>3}  {:>4}  {:<width$}  {:>10}",
+            entry.year,
+            entry.day,
+            part,
+            answer,
+            format!("{:.1?}", time),
+            width = width
+        );
+    }
+
+    let total: Duration = rows.iter().map(|row| row.3).sum();
+    println!("{:>width$}", format!("{:.1?}", total), width = width + 29);
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args {
+        [command] if command == "list" => {
+            for entry in registry() {
+                println!("{} {:>2}", entry.year, entry.day);
+            }
+
+            Ok(())
+        }
+        [command, rest @ ..] if command == "run" => {
+            let options = parse_options(rest)?;
+            let entries: Vec<Entry> = registry()
+                .into_iter()
+                .filter(|entry| entry.year == options.year)
+                .filter(|entry| options.day.is_none_or(|day| entry.day == day))
+                .collect();
+
+            if entries.is_empty() {
+                return Err(match options.day {
+                    Some(day) => format!("no solution for {} day {}", options.year, day),
+                    None => format!("no solutions for {}", options.year),
+                });
+            }
+
+            let input = match &options.input {
+                Some(path) => {
+                    Some(fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?)
+                }
+                None => None,
+            };
+
+            let parts = match options.part {
+                Some(part) => vec![part],
+                None => vec![1, 2],
+            };
+            let mut rows = Vec::new();
+
+            for entry in entries {
+                for &part in &parts {
+                    let (answer, time) =
+                        entry.solve(part, input.as_deref().unwrap_or(entry.input))?;
+                    rows.push((entry, part, answer, time));
+                }
+            }
+
+            print_table(&rows);
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(message) = run(&args) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,76 @@
+//! Advent of Code 2018.
+
+use crate::{Answer, Entry, Solution};
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    fn part1(input: &str) -> Answer {
+        aoc2018_1a::frequency(input).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc2018_1b::first_repeat(input).into()
+    }
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    fn part1(input: &str) -> Answer {
+        aoc2018_2a::checksum(input).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc2018_2b::common_letters(input).into()
+    }
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    fn part1(input: &str) -> Answer {
+        aoc2018_3a::overlap(input).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        let claims = aoc2018_3b::intact_claims(input);
+        claims.first().map(|claim| claim.id).into()
+    }
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    fn part1(input: &str) -> Answer {
+        let guard = aoc2018_4a::sleepiest(input);
+        (guard.id * guard.minute).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        let guard = aoc2018_4b::most_regular(input);
+        (guard.id * guard.minute).into()
+    }
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    fn part1(input: &str) -> Answer {
+        aoc2018_5a::reduced_length(input.as_bytes()).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc2018_5b::shortest(input).into()
+    }
+}
+
+pub fn entries() -> Vec<Entry> {
+    vec![
+        Entry::new::<Day1>(2018, 1, include_str!("../../2018/rust/01a/input")),
+        Entry::new::<Day2>(2018, 2, include_str!("../../2018/rust/02a/input")),
+        Entry::new::<Day3>(2018, 3, include_str!("../../2018/rust/03a/input")),
+        Entry::new::<Day4>(2018, 4, include_str!("../../2018/rust/04a/input")),
+        Entry::new::<Day5>(2018, 5, include_str!("../../2018/rust/05a/input")),
+    ]
+}
@@ -0,0 +1,112 @@
+//! Advent of Code 2019.
+
+use crate::{Answer, Entry, Solution};
+use intcode::{loader, MachineError};
+
+fn program(input: &str) -> Result<Vec<i64>, String> {
+    loader::parse(input).map_err(|error| format!("bad program: {}", error))
+}
+
+fn failed<T>(result: Result<T, MachineError>) -> Result<T, String> {
+    result.map_err(|error| format!("program failed: {}", error))
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    fn part1(input: &str) -> Answer {
+        aoc1a::fuel(input).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc1b::fuel(input).into()
+    }
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    fn part1(input: &str) -> Answer {
+        program(input)
+            .map(|program| aoc2a::position_zero(&program))
+            .into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        program(input)
+            .map(|program| aoc2b::noun_verb(&program).map(|(noun, verb)| 100 * noun + verb))
+            .into()
+    }
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    fn part1(input: &str) -> Answer {
+        aoc3a::distance(input).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc3b::distance(input).into()
+    }
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    fn part1(input: &str) -> Answer {
+        aoc4a::passwords(input).len().into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc4b::count(input).into()
+    }
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    fn part1(input: &str) -> Answer {
+        program(input)
+            .and_then(|program| failed(aoc5a::diagnostic_code(&program, 1)))
+            .into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        program(input)
+            .and_then(|program| failed(aoc5b::diagnostic_code(&program, 5)))
+            .into()
+    }
+}
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    fn part1(input: &str) -> Answer {
+        program(input)
+            .map(|program| aoc7a::max_thruster(&program).1)
+            .into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        program(input)
+            .and_then(|program| {
+                aoc7b::search(&aoc7b::amplifiers(&program))
+                    .map_err(|error| format!("amplifiers failed: {}", error))
+            })
+            .map(|search| search.best.map(|(_, signal)| signal))
+            .into()
+    }
+}
+
+pub fn entries() -> Vec<Entry> {
+    vec![
+        Entry::new::<Day1>(2019, 1, include_str!("../../2019/rust/01a/input")),
+        Entry::new::<Day2>(2019, 2, include_str!("../../2019/rust/02a/input")),
+        Entry::new::<Day3>(2019, 3, include_str!("../../2019/rust/03a/input")),
+        // The input file for day 4 is a copy of day 3's; the real input is in the puzzle text.
+        Entry::new::<Day4>(2019, 4, aoc4a::INPUT),
+        Entry::new::<Day5>(2019, 5, include_str!("../../2019/rust/05a/input")),
+        Entry::new::<Day7>(2019, 7, include_str!("../../2019/rust/07a/input")),
+    ]
+}
@@ -0,0 +1,34 @@
+//! Advent of Code 2020.
+
+use crate::{Answer, Entry, Solution};
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    fn part1(input: &str) -> Answer {
+        aoc1::product(input, 2).into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc1::product(input, 3).into()
+    }
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    fn part1(input: &str) -> Answer {
+        aoc2::check(input).valid.into()
+    }
+
+    fn part2(input: &str) -> Answer {
+        aoc2::check(input).v2valid.into()
+    }
+}
+
+pub fn entries() -> Vec<Entry> {
+    vec![
+        Entry::new::<Day1>(2020, 1, include_str!("../../2020/rust/01/input")),
+        Entry::new::<Day2>(2020, 2, include_str!("../../2020/rust/02/input")),
+    ]
+}